@@ -166,7 +166,8 @@ impl Solver {
         let mut path = Path::new(0.0, Vec::new());
         let mut last = adj_b.reduced_id;
         let mut previous = self.adj_matrix[adj_a.reduced_id][last].get_p();
-        while previous != last {
+        // The walk ends at the node the paths start from, which stays put
+        while previous != last && previous != Msg::STAY {
           path = &self.path_along_edge(self.reduced_mapping[previous], self.reduced_mapping[last])
             + &path;
           dbg!(&path);
@@ -6,7 +6,7 @@ use std::num::{ParseFloatError, ParseIntError};
 use std::rc::Rc;
 use std::cell::RefCell;
 
-use sim::matmul::Multiplicable;
+use sim::matmul::Semiring;
 use sim::types::{Matrix, Msg};
 
 use crate::adj_matrix::Store;
@@ -174,7 +174,7 @@ pub trait ToAdj {
 impl ToAdj for Vec<Rc<Edge>> {
   fn into_adj(&self) -> Matrix<Msg> {
     let num_nodes = self.num_nodes();
-    let mut adj = Msg::neutral_matrix(num_nodes, num_nodes);
+    let mut adj = Msg::identity_matrix(num_nodes);
 
     for edge in self.iter() {
       adj[edge.node_a][edge.node_b] = Msg::new(edge.distance, edge.node_a);
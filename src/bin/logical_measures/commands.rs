@@ -1,35 +1,130 @@
-use std::{any::type_name, time::Duration};
+use std::{any::type_name, fmt::Display, time::Duration};
 
-use sim::matmul::ProbeMatMul;
-use sim::matmul::comm_method::{Hash, CommMethod, FoxOtto, Cannon, PipeFoxOtto};
+use sim::matmul::MatMul;
+use sim::matmul::comm_method::{Hash, CommMethod, FoxOtto, Cannon, PipeFoxOtto, Summa, Dns, Cannon25D};
 use sim::types::Matrix;
-use sim::processor::taurus::{TimeTaurusNetworkBuilder, TimedTaurusCore};
-use sim::processor::probe::ThreadTimeProber;
-use sim::processor::ProbeProcessor;
+use sim::processor::taurus::{TimeTaurusNetworkBuilder, TimedTaurusCore, TaurusOption};
+use sim::processor::probe::Prober;
+use sim::processor::{ProbeProcessor, Core};
 use crate::bench::{Run, Bench, Group};
-use crate::ITERATIONS;
+use crate::{ITERATIONS, VERIFY};
 
+type TimedCore = TimedTaurusCore<(Matrix<isize>,Duration)>;
 
-pub fn against_processor<T>(proc_sizes : impl Iterator<Item = usize>,
+/// Layers of a two dimensional method, which uses a single grid
+pub fn flat(_ : usize) -> usize {
+  1
+}
+
+/// Layers of DNS, which needs as many layers as the grid is wide
+pub fn cube(side : usize) -> usize {
+  side
+}
+
+/// Layers of 2.5D Cannon, at most `layers` and never more than the grid is wide
+pub fn depth(layers : usize) -> impl Fn(usize) -> usize + Clone {
+  move |side| layers.min(side)
+}
+
+/// Panel widths of SUMMA the benchmarks can run with
+pub const PANELS : [usize; 5] = [8, 16, 32, 64, 128];
+
+/// `against_processor` for SUMMA with panels `panel` wide, one of `PANELS`
+pub fn summa_against_processor<P>(panel : usize, proc_sizes : impl Iterator<Item = usize>, matrix_size : usize,
+                                  network_builder : TimeTaurusNetworkBuilder) -> Bench
+where P : Prober<Duration, (Matrix<isize>,Duration), TimedCore> + Core<Matrix<isize>, ChannelOption = TaurusOption> {
+  match panel {
+    8 => against_processor::<Summa<8>, P>(proc_sizes, matrix_size, network_builder, flat),
+    16 => against_processor::<Summa<16>, P>(proc_sizes, matrix_size, network_builder, flat),
+    32 => against_processor::<Summa<32>, P>(proc_sizes, matrix_size, network_builder, flat),
+    64 => against_processor::<Summa<64>, P>(proc_sizes, matrix_size, network_builder, flat),
+    128 => against_processor::<Summa<128>, P>(proc_sizes, matrix_size, network_builder, flat),
+    _ => unreachable!("SUMMA runs with the panel widths in PANELS, not {panel}"),
+  }
+}
+
+/// `against_matrices` for SUMMA with panels `panel` wide, one of `PANELS`
+pub fn summa_against_matrices<P>(panel : usize, proc_size : usize, matrix_sizes : impl Iterator<Item = usize>,
+                                 network_builder : TimeTaurusNetworkBuilder) -> Bench
+where P : Prober<Duration, (Matrix<isize>,Duration), TimedCore> + Core<Matrix<isize>, ChannelOption = TaurusOption> {
+  match panel {
+    8 => against_matrices::<Summa<8>, P>(proc_size, matrix_sizes, network_builder, flat),
+    16 => against_matrices::<Summa<16>, P>(proc_size, matrix_sizes, network_builder, flat),
+    32 => against_matrices::<Summa<32>, P>(proc_size, matrix_sizes, network_builder, flat),
+    64 => against_matrices::<Summa<64>, P>(proc_size, matrix_sizes, network_builder, flat),
+    128 => against_matrices::<Summa<128>, P>(proc_size, matrix_sizes, network_builder, flat),
+    _ => unreachable!("SUMMA runs with the panel widths in PANELS, not {panel}"),
+  }
+}
+
+/// Exits with the reason `T` cannot multiply `matrix_size` x `matrix_size`
+/// matrices on `processor_size` x `processor_size` cores, such as Strassen
+/// given a size it cannot halve
+fn unsupported<T>(matrix_size : usize, processor_size : usize, error : impl Display) -> ! {
+  eprintln!("{} cannot multiply {matrix_size}x{matrix_size} matrices on {processor_size}x{processor_size} cores: {error}",
+            type_name::<T>());
+  std::process::exit(1);
+}
+
+/// Squarings each timed run does of a `matrix_size` x `matrix_size` matrix
+fn squarings(matrix_size : usize) -> usize {
+  f64::ceil(f64::log2(matrix_size as f64)) as usize
+}
+
+/// With `--verify`, checks that `T` squares correctly `iterations` times on
+/// `layers` layers of `processor_size` x `processor_size` cores, like the
+/// timed runs, exiting with the report of the first wrong element if it does
+/// not. The timed runs square matrices of zeros, so this squares a shift
+/// with mixed signs instead, whose powers move every element and never
+/// outgrow an `isize`.
+fn verify<T, P>(matrix_size : usize, processor_size : usize, iterations : usize,
+                network_builder : &TimeTaurusNetworkBuilder, layers : usize)
+where T : CommMethod<isize, P>,
+      P : Prober<Duration, (Matrix<isize>,Duration), TimedCore> + Core<Matrix<isize>> {
+  let verify : bool;
+  unsafe {
+    verify = VERIFY;
+  }
+  if !verify {
+    return;
+  }
+  let a : Matrix<isize> = (0..matrix_size).map(|i| (0..matrix_size).map(|j|
+    if j == (i + 1) % matrix_size { if i % 3 == 0 { -1 } else { 1 } } else { 0 }
+  ).collect()).collect();
+  let mut processor : ProbeProcessor <Duration, Matrix<isize>,(Matrix<isize>,Duration), TimedCore> = 
+    ProbeProcessor::new(layers * processor_size, processor_size, network_builder.clone().with_layers(layers));
+  let mut matmul : MatMul<isize, P, _> = MatMul::new(&mut processor).with_verification();
+  if let Err(error) = matmul.try_parallel_square::<T>(a, iterations) {
+    eprintln!("Verification of {} with {matrix_size}x{matrix_size} matrices failed: {error}", type_name::<T>());
+    std::process::exit(1);
+  }
+}
+
+pub fn against_processor<T, P>(proc_sizes : impl Iterator<Item = usize>,
                             matrix_size : usize,
-                            network_builder : TimeTaurusNetworkBuilder) -> Bench
-where T : CommMethod<isize, ThreadTimeProber<Matrix<isize>, TimedTaurusCore<(Matrix<isize>,Duration)>>> {
+                            network_builder : TimeTaurusNetworkBuilder,
+                            layers : impl Fn(usize) -> usize) -> Bench
+where T : CommMethod<isize, P>,
+      P : Prober<Duration, (Matrix<isize>,Duration), TimedCore> + Core<Matrix<isize>> {
   let mut bench = Bench::new(format!("{} vs Processor", type_name::<T>()));
   println!("Running {bench}");
   for processor_size in proc_sizes {
     let mut run = Run::new(matrix_size, processor_size);
+    let iterations = squarings(matrix_size);
+    verify::<T, P>(matrix_size, processor_size, iterations, &network_builder, layers(processor_size));
     let iter : usize;
     unsafe {
       iter = ITERATIONS;
     }
     for _ in 0..iter {
       let a = vec![vec![0; matrix_size]; matrix_size];
-      let iterations = f64::ceil(f64::log2(a.len() as f64)) as usize;
-      let mut processor : ProbeProcessor <Duration, (usize,usize,Matrix<isize>),(Matrix<isize>,Duration), TimedTaurusCore<(Matrix<isize>,Duration)>> = 
-        ProbeProcessor::new(processor_size, processor_size, network_builder);
-      let mut matmul : ProbeMatMul<isize, Duration, (Matrix<isize>, Duration),
-      TimedTaurusCore<(Matrix<isize>,Duration)>> = ProbeMatMul::new(&mut processor);
-      matmul.parallel_square::<T, ThreadTimeProber<Matrix<isize>,TimedTaurusCore<(Matrix<isize>,Duration)>>>(a,iterations);
+      let mut processor : ProbeProcessor <Duration, Matrix<isize>,(Matrix<isize>,Duration), TimedCore> = 
+        ProbeProcessor::new(layers(processor_size) * processor_size, processor_size,
+                            network_builder.clone().with_layers(layers(processor_size)));
+      let mut matmul : MatMul<isize, P, _> = MatMul::new(&mut processor);
+      if let Err(error) = matmul.try_parallel_square::<T>(a,iterations) {
+        unsupported::<T>(matrix_size, processor_size, error);
+      }
       match processor.max_debug_time() {
         Some(time) => run.data.push(time),
         _ => ()
@@ -40,38 +135,48 @@ where T : CommMethod<isize, ThreadTimeProber<Matrix<isize>, TimedTaurusCore<(Mat
   bench
 }
 
-pub fn against_processor_all(proc_sizes : impl Iterator<Item = usize> + Clone
+pub fn against_processor_all<P>(proc_sizes : impl Iterator<Item = usize> + Clone
                              , matrix_size : usize,
-                             network_builder : TimeTaurusNetworkBuilder) -> Group {
+                             network_builder : TimeTaurusNetworkBuilder,
+                             layers : usize, panel : usize) -> Group
+where P : Prober<Duration, (Matrix<isize>,Duration), TimedCore> + Core<Matrix<isize>, ChannelOption = TaurusOption> {
   let mut group = Group::new(format!("All vs Processor"));
   println!("Running {group}");
-  group.data.push(against_processor::<Hash>(proc_sizes.clone(), matrix_size, network_builder));
-  group.data.push(against_processor::<FoxOtto>(proc_sizes.clone(), matrix_size, network_builder));
-  group.data.push(against_processor::<Cannon>(proc_sizes.clone(), matrix_size, network_builder));
-  group.data.push(against_processor::<PipeFoxOtto>(proc_sizes.clone(), matrix_size, network_builder));
+  group.data.push(against_processor::<Hash, P>(proc_sizes.clone(), matrix_size, network_builder.clone(), flat));
+  group.data.push(against_processor::<FoxOtto, P>(proc_sizes.clone(), matrix_size, network_builder.clone(), flat));
+  group.data.push(against_processor::<Cannon, P>(proc_sizes.clone(), matrix_size, network_builder.clone(), flat));
+  group.data.push(against_processor::<PipeFoxOtto, P>(proc_sizes.clone(), matrix_size, network_builder.clone(), flat));
+  group.data.push(summa_against_processor::<P>(panel, proc_sizes.clone(), matrix_size, network_builder.clone()));
+  group.data.push(against_processor::<Dns, P>(proc_sizes.clone(), matrix_size, network_builder.clone(), cube));
+  group.data.push(against_processor::<Cannon25D, P>(proc_sizes.clone(), matrix_size, network_builder.clone(), depth(layers)));
   group
 }
 
-pub fn against_matrices<T>(proc_size : usize,
+pub fn against_matrices<T, P>(proc_size : usize,
                            matrix_sizes : impl Iterator<Item = usize>
-                           , network_builder : TimeTaurusNetworkBuilder) -> Bench
-where T : CommMethod<isize, ThreadTimeProber<Matrix<isize>, TimedTaurusCore<(Matrix<isize>,Duration)>>> {
+                           , network_builder : TimeTaurusNetworkBuilder,
+                           layers : impl Fn(usize) -> usize) -> Bench
+where T : CommMethod<isize, P>,
+      P : Prober<Duration, (Matrix<isize>,Duration), TimedCore> + Core<Matrix<isize>> {
   let mut bench = Bench::new(format!("{} vs Matrices", type_name::<T>()));
   println!("Running {bench}");
   for matrix_size in matrix_sizes {
     let mut run = Run::new(matrix_size, proc_size);
+    let iterations = squarings(matrix_size);
+    verify::<T, P>(matrix_size, proc_size, iterations, &network_builder, layers(proc_size));
     let iter : usize;
     unsafe {
       iter = ITERATIONS;
     }
     for _ in 0..iter {
       let a = vec![vec![0; matrix_size]; matrix_size];
-      let iterations = f64::ceil(f64::log2(a.len() as f64)) as usize;
-      let mut processor : ProbeProcessor <Duration, (usize,usize,Matrix<isize>),(Matrix<isize>,Duration), TimedTaurusCore<(Matrix<isize>,Duration)>> = 
-        ProbeProcessor::new(proc_size, proc_size, network_builder);
-      let mut matmul : ProbeMatMul<isize, Duration, (Matrix<isize>, Duration),
-      TimedTaurusCore<(Matrix<isize>,Duration)>> = ProbeMatMul::new(&mut processor);
-      matmul.parallel_square::<T, ThreadTimeProber<Matrix<isize>,TimedTaurusCore<(Matrix<isize>,Duration)>>>(a,iterations);
+      let mut processor : ProbeProcessor <Duration, Matrix<isize>,(Matrix<isize>,Duration), TimedCore> = 
+        ProbeProcessor::new(layers(proc_size) * proc_size, proc_size,
+                            network_builder.clone().with_layers(layers(proc_size)));
+      let mut matmul : MatMul<isize, P, _> = MatMul::new(&mut processor);
+      if let Err(error) = matmul.try_parallel_square::<T>(a,iterations) {
+        unsupported::<T>(matrix_size, proc_size, error);
+      }
       match processor.max_debug_time() {
         Some(time) => run.data.push(time),
         _ => ()
@@ -82,14 +187,19 @@ where T : CommMethod<isize, ThreadTimeProber<Matrix<isize>, TimedTaurusCore<(Mat
   bench
 }
 
-pub fn against_matrices_all(proc_size : usize, 
+pub fn against_matrices_all<P>(proc_size : usize, 
                             matrix_sizes : impl Iterator<Item=usize> + Clone,
-                            network_builder : TimeTaurusNetworkBuilder) -> Group {
+                            network_builder : TimeTaurusNetworkBuilder,
+                            layers : usize, panel : usize) -> Group
+where P : Prober<Duration, (Matrix<isize>,Duration), TimedCore> + Core<Matrix<isize>, ChannelOption = TaurusOption> {
   let mut group = Group::new(format!("All vs Matrices"));
   println!("Running {group}");
-  group.data.push(against_matrices::<Hash>(proc_size, matrix_sizes.clone(), network_builder));
-  group.data.push(against_matrices::<FoxOtto>(proc_size, matrix_sizes.clone(),network_builder));
-  group.data.push(against_matrices::<Cannon>(proc_size, matrix_sizes.clone(),network_builder));
-  group.data.push(against_matrices::<PipeFoxOtto>(proc_size, matrix_sizes.clone(),network_builder));
+  group.data.push(against_matrices::<Hash, P>(proc_size, matrix_sizes.clone(), network_builder.clone(), flat));
+  group.data.push(against_matrices::<FoxOtto, P>(proc_size, matrix_sizes.clone(), network_builder.clone(), flat));
+  group.data.push(against_matrices::<Cannon, P>(proc_size, matrix_sizes.clone(), network_builder.clone(), flat));
+  group.data.push(against_matrices::<PipeFoxOtto, P>(proc_size, matrix_sizes.clone(), network_builder.clone(), flat));
+  group.data.push(summa_against_matrices::<P>(panel, proc_size, matrix_sizes.clone(), network_builder.clone()));
+  group.data.push(against_matrices::<Dns, P>(proc_size, matrix_sizes.clone(), network_builder.clone(), cube));
+  group.data.push(against_matrices::<Cannon25D, P>(proc_size, matrix_sizes.clone(), network_builder.clone(), depth(layers)));
   group
 }
@@ -1,13 +1,19 @@
 use clap_derive::ValueEnum;
-use sim::matmul::comm_method::{Hash, FoxOtto, Cannon, PipeFoxOtto};
-use sim::processor::taurus::TimeTaurusNetworkBuilder;
+use sim::matmul::comm_method::{Hash, FoxOtto, Cannon, PipeFoxOtto, Dns, Cannon25D, Strassen};
+use sim::processor::taurus::{TimeTaurusNetworkBuilder, TimedTaurusCore, TaurusOption};
+use sim::processor::taurus::link::{Link, LinkTable};
+use sim::processor::probe::{Prober, ThreadTimeProber, WallTimeProber};
+use sim::processor::Core;
+use sim::types::Matrix;
+use std::time::Duration;
 use std::fs::File;
 use std::io::prelude::*;
 
 mod bench;
 mod commands;
 use bench::Group;
-use commands::{against_matrices, against_processor, against_matrices_all, against_processor_all};
+use commands::{against_matrices, against_processor, against_matrices_all, against_processor_all, flat, cube, depth};
+use commands::{summa_against_matrices, summa_against_processor, PANELS};
 
 use clap::{Parser, Subcommand};
 
@@ -34,6 +40,26 @@ pub struct Cli {
     /// Startup of broadcast
     #[arg(short, long, default_value_t = 1)]
     startup : usize,
+
+    /// JSON file of per-link latency and bandwidth overrides
+    #[arg(long, conflicts_with = "tile_rows")]
+    links : Option<String>,
+
+    /// Rows of cores per tile (chip), enables the two-level network
+    #[arg(long, requires = "tile_cols")]
+    tile_rows : Option<usize>,
+
+    /// Columns of cores per tile (chip)
+    #[arg(long, requires = "tile_rows")]
+    tile_cols : Option<usize>,
+
+    /// Latency in ns of links between tiles
+    #[arg(long, default_value_t = 1000)]
+    inter_latency : usize,
+
+    /// Bandwidth in B/ns of links between tiles
+    #[arg(long, default_value_t = 10000000)]
+    inter_bandwidth : usize,
     
     /// File to write json to
     #[arg(short, long, default_value_t = String::from("data.json"))]
@@ -43,6 +69,22 @@ pub struct Cli {
     #[arg(short, long, default_value_t = 20)]
     iter: usize,
 
+    /// Most layers of cores used by 2.5D Cannon
+    #[arg(long, default_value_t = 2)]
+    layers: usize,
+
+    /// Width of the panels SUMMA broadcasts
+    #[arg(long, default_value_t = 32, value_parser = panel_width)]
+    panel: usize,
+
+    /// Clock used to measure the time of each core
+    #[arg(long, value_enum, default_value_t = CliClock::Thread)]
+    clock: CliClock,
+
+    /// Check every method against the serial product before timing it
+    #[arg(long)]
+    verify: bool,
+
 }
 
 #[derive(Subcommand)]
@@ -88,7 +130,15 @@ enum CliComm {
   /// Cannon
   Cannon,
   /// Pipelined FoxOtto
-  PipeFoxOtto
+  PipeFoxOtto,
+  /// SUMMA with panels `--panel` wide
+  Summa,
+  /// DNS on a cube of cores
+  Dns,
+  /// 2.5D Cannon on `--layers` layers of cores
+  Cannon25D,
+  /// One level of Strassen-Winograd over Cannon, needs even matrix sizes
+  Strassen,
 }
 
 impl CliComm {
@@ -98,43 +148,116 @@ impl CliComm {
       Self::FoxOtto => "FoxOtto",
       Self::Cannon => "Cannon",
       Self::PipeFoxOtto => "Pipeline FoxOtto",
+      Self::Summa => "SUMMA",
+      Self::Dns => "DNS",
+      Self::Cannon25D => "2.5D Cannon",
+      Self::Strassen => "Strassen-Winograd",
     }
   }
 }
   
 
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum CliClock {
+  /// CPU time of the thread running each core
+  Thread,
+  /// Wall-clock time
+  Wall,
+}
+
+type TimedCore = TimedTaurusCore<(Matrix<isize>,Duration)>;
+
+/// Parses `--panel`, which must be one of the widths in `PANELS`
+fn panel_width(arg : &str) -> Result<usize, String> {
+  let width : usize = arg.parse().map_err(|error| format!("{error}"))?;
+  if PANELS.contains(&width) {
+    Ok(width)
+  } else {
+    Err(format!("SUMMA runs with panels {PANELS:?} wide"))
+  }
+}
+
 static mut ITERATIONS : usize = 20;
+static mut VERIFY : bool = false;
 
 fn main() -> std::io::Result<()> {
   let cli = Cli::parse();
 
   unsafe {
     ITERATIONS = cli.iter;
+    VERIFY = cli.verify;
   }
 
   let network_builder = TimeTaurusNetworkBuilder::new(cli.latency, cli.bandwidth, cli.startup);
-  let group = match cli.command {
+  let network_builder = match &cli.links {
+    None => network_builder,
+    Some(path) => network_builder.with_link_table(LinkTable::from_json_file(path)?),
+  };
+  let network_builder = match (cli.tile_rows, cli.tile_cols) {
+    (Some(tile_rows), Some(tile_cols)) => network_builder.with_tiles(
+      tile_rows, tile_cols,
+      Link::new(cli.latency, cli.bandwidth),
+      Link::new(cli.inter_latency, cli.inter_bandwidth)),
+    _ => network_builder,
+  };
+  let group = match cli.clock {
+    CliClock::Thread => run::<ThreadTimeProber<Matrix<isize>, TimedCore>>(&cli, network_builder),
+    CliClock::Wall => run::<WallTimeProber<Matrix<isize>, TimedCore>>(&cli, network_builder),
+  };
+
+  // Convert the data to JSON format
+  let json_data = serde_json::to_string(&group)?;
+
+  // Write the JSON data to a file
+  let mut file = File::create(&cli.output)?;
+  file.write_all(json_data.as_bytes())?;
+
+  println!("Data has been written to {}", &cli.output);
+  Ok(())
+}
+
+/// Runs the benchmark selected by `cli`, measuring each core with the
+/// prober `P`
+fn run<P>(cli : &Cli, network_builder : TimeTaurusNetworkBuilder) -> Group
+where P : Prober<Duration, (Matrix<isize>,Duration), TimedCore> + Core<Matrix<isize>, ChannelOption = TaurusOption> {
+  match cli.command {
     Command::Matrix { start, end, step, proc} => {
       let matrix_sizes = (start..=end).step_by(step);
       match cli.comm {
-        None => against_matrices_all(proc, matrix_sizes, network_builder),
+        None => against_matrices_all::<P>(proc, matrix_sizes, network_builder, cli.layers, cli.panel),
         Some(comm) => {
           let mut g = Group::new(format!("{} vs Matrix size", comm.display()));
           match comm {
             CliComm::Hash => {
-              g.data.push(against_matrices::<Hash>(proc, matrix_sizes,network_builder));
+              g.data.push(against_matrices::<Hash, P>(proc, matrix_sizes, network_builder, flat));
               g
             },
             CliComm::FoxOtto => {
-              g.data.push(against_matrices::<FoxOtto>(proc, matrix_sizes,network_builder));
+              g.data.push(against_matrices::<FoxOtto, P>(proc, matrix_sizes, network_builder, flat));
               g
             },
             CliComm::Cannon => {
-              g.data.push(against_matrices::<Cannon>(proc, matrix_sizes,network_builder));
+              g.data.push(against_matrices::<Cannon, P>(proc, matrix_sizes, network_builder, flat));
               g
             },
             CliComm::PipeFoxOtto => {
-              g.data.push(against_matrices::<PipeFoxOtto>(proc, matrix_sizes,network_builder));
+              g.data.push(against_matrices::<PipeFoxOtto, P>(proc, matrix_sizes, network_builder, flat));
+              g
+            },
+            CliComm::Summa => {
+              g.data.push(summa_against_matrices::<P>(cli.panel, proc, matrix_sizes, network_builder));
+              g
+            },
+            CliComm::Dns => {
+              g.data.push(against_matrices::<Dns, P>(proc, matrix_sizes, network_builder, cube));
+              g
+            },
+            CliComm::Cannon25D => {
+              g.data.push(against_matrices::<Cannon25D, P>(proc, matrix_sizes, network_builder, depth(cli.layers)));
+              g
+            },
+            CliComm::Strassen => {
+              g.data.push(against_matrices::<Strassen<Cannon>, P>(proc, matrix_sizes, network_builder, flat));
               g
             }
           }
@@ -144,39 +267,45 @@ fn main() -> std::io::Result<()> {
     Command::Processor { start, end, step, matrix} => {
       let proc_sizes = (start..=end).step_by(step).map(|x| 2_i32.pow(x as u32) as usize);
       match cli.comm {
-        None => against_processor_all(proc_sizes, matrix, network_builder),
+        None => against_processor_all::<P>(proc_sizes, matrix, network_builder, cli.layers, cli.panel),
         Some(comm) => {
           let mut g = Group::new(format!("{} vs Processor size", comm.display()));
           match comm {
             CliComm::Hash => {
-              g.data.push(against_processor::<Hash>(proc_sizes, matrix, network_builder));
+              g.data.push(against_processor::<Hash, P>(proc_sizes, matrix, network_builder, flat));
               g
             },
             CliComm::FoxOtto => {
-              g.data.push(against_processor::<FoxOtto>(proc_sizes, matrix, network_builder));
+              g.data.push(against_processor::<FoxOtto, P>(proc_sizes, matrix, network_builder, flat));
               g
             },
             CliComm::Cannon => {
-              g.data.push(against_processor::<Cannon>(proc_sizes, matrix, network_builder));
+              g.data.push(against_processor::<Cannon, P>(proc_sizes, matrix, network_builder, flat));
               g
             },
             CliComm::PipeFoxOtto => {
-              g.data.push(against_processor::<PipeFoxOtto>(proc_sizes, matrix, network_builder));
+              g.data.push(against_processor::<PipeFoxOtto, P>(proc_sizes, matrix, network_builder, flat));
+              g
+            },
+            CliComm::Summa => {
+              g.data.push(summa_against_processor::<P>(cli.panel, proc_sizes, matrix, network_builder));
+              g
+            },
+            CliComm::Dns => {
+              g.data.push(against_processor::<Dns, P>(proc_sizes, matrix, network_builder, cube));
+              g
+            },
+            CliComm::Cannon25D => {
+              g.data.push(against_processor::<Cannon25D, P>(proc_sizes, matrix, network_builder, depth(cli.layers)));
+              g
+            },
+            CliComm::Strassen => {
+              g.data.push(against_processor::<Strassen<Cannon>, P>(proc_sizes, matrix, network_builder, flat));
               g
             }
           }
         }
       }
     }
-  };
-
-  // Convert the data to JSON format
-  let json_data = serde_json::to_string(&group)?;
-
-  // Write the JSON data to a file
-  let mut file = File::create(&cli.output)?;
-  file.write_all(json_data.as_bytes())?;
-
-  println!("Data has been written to {}", &cli.output);
-  Ok(())
+  }
 }
@@ -1,13 +1,34 @@
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::fmt::Debug;
+use std::time::Duration;
 
 
-pub trait Sendable : Clone + Debug + std::marker::Send{}
+pub trait Sendable : Clone + Debug + std::marker::Send{
+  /// Bytes of data held by the value. Defaults to its size on the stack,
+  /// containers add up the payload of their elements.
+  fn payload_size(&self) -> usize {
+    std::mem::size_of_val(self)
+  }
+  /// Bytes sent over a link to transmit the value, which is its payload
+  /// unless it has a more compact encoding on the wire
+  fn message_size(&self) -> usize {
+    self.payload_size()
+  }
+  /// Whether the value carries nothing, so that containers may leave it out
+  /// of their compact encodings. Semirings report their zero.
+  fn is_blank(&self) -> bool {
+    false
+  }
+}
 
 pub trait Channel<T:Sendable> {
   fn send(&self, data : T);
   fn recv(&self) -> T;
+  /// Waits at most `timeout` for a message. Fails with `Timeout` if none
+  /// arrived, or with `Disconnected` if the senders have all gone and none
+  /// ever can.
+  fn recv_timeout(&self, timeout : Duration) -> Result<T, mpsc::RecvTimeoutError>;
 }
 
 pub struct Broadcast<T : Sendable> {
@@ -58,6 +79,10 @@ impl<T:Sendable> Channel<T> for Broadcast<T> {
   fn recv(&self) -> T {
      self.rx.recv().unwrap()
   }
+
+  fn recv_timeout(&self, timeout : Duration) -> Result<T, mpsc::RecvTimeoutError> {
+    self.rx.recv_timeout(timeout)
+  }
 }
 
 pub struct Direct<T : Sendable> {
@@ -91,6 +116,10 @@ impl<T:Sendable> Channel<T> for Direct<T> {
   fn recv(&self) -> T {
     self.rx.recv().unwrap()
   }
+
+  fn recv_timeout(&self, timeout : Duration) -> Result<T, mpsc::RecvTimeoutError> {
+    self.rx.recv_timeout(timeout)
+  }
 }
 #[cfg(test)]
 mod tests;
@@ -1,6 +1,6 @@
 use std::{thread, sync::mpsc};
 
-use super::{Broadcast, Sendable, Channel};
+use super::{Broadcast, Direct, Sendable, Channel};
 
 impl Sendable for i32 {}
 impl Sendable for String {}
@@ -105,3 +105,12 @@ fn test_inorder_parallel(){
     assert_eq!(value, receivers[2].recv());
   }
 }
+
+#[test]
+fn test_recv_timeout_reports_disconnect(){
+  let (near, far) : (Direct<i32>, Direct<i32>) = Direct::new();
+  let timeout = std::time::Duration::from_millis(1);
+  assert_eq!(near.recv_timeout(timeout), Err(mpsc::RecvTimeoutError::Timeout));
+  drop(far);
+  assert_eq!(near.recv_timeout(timeout), Err(mpsc::RecvTimeoutError::Disconnected));
+}
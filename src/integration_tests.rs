@@ -1,17 +1,19 @@
 use std::time::Duration;
 
-use crate::matmul::{ProbeMatMul, MatMul, comm_method::{Hash, FoxOtto, Cannon, PipeFoxOtto}};
+use crate::matmul::{MatMul, comm_method::{Hash, FoxOtto, Cannon, PipeFoxOtto}};
 use crate::processor::probe::ThreadTimeProber;
 use crate:: processor::taurus::{TaurusNetworkBuilder, TimeTaurusNetworkBuilder, TaurusCore, TimedTaurusCore};
 use crate::processor::{Processor, ProbeProcessor};
 use crate::types::{Matrix, Msg};
 
+type Prober = ThreadTimeProber<Matrix<isize>, TimedTaurusCore<(Matrix<isize>, Duration)>>;
+
 #[test]
 #[ignore]
 fn test_hash_matrix_mult_api() {
   
   let network_builder = TaurusNetworkBuilder;
-  let mut processor : Processor <(usize, usize, Matrix<isize>), Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
     Processor::new(2,2, network_builder);
   let mut p : MatMul<isize> = MatMul::new(&mut processor);
 
@@ -42,7 +44,7 @@ fn test_hash_matrix_mult_api() {
 fn test_fox_otto_matrix_mult() {
   let network_builder = TimeTaurusNetworkBuilder::new(0, 1, 0);
   let mut processor = ProbeProcessor::new(2,2, network_builder);
-  let mut p = ProbeMatMul::new(&mut processor);
+  let mut p : MatMul<isize, Prober, _> = MatMul::new(&mut processor);
   
   let matrix_a: Matrix<isize> = vec![
     vec![1,2,3],
@@ -56,9 +58,7 @@ fn test_fox_otto_matrix_mult() {
     vec![3,2,1],
   ];
 
-  let c = p.parallel_mult::
-    <FoxOtto, ThreadTimeProber<Matrix<isize>, TimedTaurusCore<(Matrix<isize>, Duration)>>>
-    (matrix_a, matrix_b);
+  let c = p.parallel_mult::<FoxOtto>(matrix_a, matrix_b);
 
   assert_eq!(c, vec![
     vec![30,24,18],
@@ -72,7 +72,7 @@ fn test_fox_otto_matrix_mult() {
 #[ignore]
 fn test_cannon_matrix_mult() {
   let network_builder = TaurusNetworkBuilder;
-  let mut processor : Processor <(usize, usize, Matrix<isize>), Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
     Processor::new(2,2, network_builder);
   let mut p : MatMul<isize> = MatMul::new(&mut processor);
   
@@ -103,7 +103,7 @@ fn test_cannon_matrix_mult() {
 fn test_pipefoxotto_matrix_mult() {
   let network_builder = TimeTaurusNetworkBuilder::new(0, 1, 0);
   let mut processor = ProbeProcessor::new(2,2, network_builder);
-  let mut p = ProbeMatMul::new(&mut processor);
+  let mut p : MatMul<isize, Prober, _> = MatMul::new(&mut processor);
   
   let matrix_a: Matrix<isize> = vec![
     vec![1,2,3],
@@ -117,9 +117,7 @@ fn test_pipefoxotto_matrix_mult() {
     vec![3,2,1],
   ];
 
-  let c = p.parallel_mult::
-    <PipeFoxOtto, ThreadTimeProber<Matrix<isize>, TimedTaurusCore<(Matrix<isize>, Duration)>>>
-    (matrix_a, matrix_b);
+  let c = p.parallel_mult::<PipeFoxOtto>(matrix_a, matrix_b);
 
   assert_eq!(c, vec![
     vec![30,24,18],
@@ -132,7 +130,7 @@ fn test_pipefoxotto_matrix_mult() {
 #[ignore]
 fn test_pipefoxotto_matrix_mult2() {
   let network_builder = TaurusNetworkBuilder;
-  let mut processor : Processor <(usize, usize, Matrix<isize>), Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
     Processor::new(2,2, network_builder);
   let mut p : MatMul<isize> = MatMul::new(&mut processor);
   
@@ -161,7 +159,7 @@ fn test_pipefoxotto_matrix_mult2() {
 #[ignore]
 fn test_fox_otto_matrix_mult_with_reduction() {
   let network_builder = TaurusNetworkBuilder;
-  let mut processor : Processor <(usize, usize, Matrix<Msg>), Matrix<Msg>, TaurusCore<Matrix<Msg>>> = 
+  let mut processor : Processor <Matrix<Msg>, Matrix<Msg>, TaurusCore<Matrix<Msg>>> = 
     Processor::new(2,2, network_builder);
   let mut p : MatMul<Msg> = MatMul::new(&mut processor);
   
@@ -1,19 +1,89 @@
 use std::collections::VecDeque;
-use crate::processor::{taurus::TaurusOption, Core, get_submatrices};
+use std::marker::PhantomData;
+use crate::processor::{taurus::TaurusOption, Core, distribution::{Distribution, Skew}};
 use crate::broadcast::Sendable;
 use crate::types::Matrix;
-use super::{Multiplicable, serial_matmul};
+use super::{Semiring, Ring, ShapeError, serial_matmul, mul_add_blocks, dense::DenseMatrix};
 
+/// Shape of a multiplication of a `rows` x `inner` matrix by an `inner` x
+/// `cols` matrix, and how both are laid out over the grid. Every core gets a
+/// copy, so methods can work out which parts of the matrices other cores
+/// hold.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+  pub distribution : Distribution,
+  pub rows : usize,
+  pub inner : usize,
+  pub cols : usize,
+}
+
+impl Layout {
+  pub fn new(distribution : Distribution, rows : usize, inner : usize, cols : usize) -> Self {
+    Layout { distribution, rows, inner, cols }
+  }
+
+  /// Layout of the product of `matrix_a` and `matrix_b`
+  pub fn of<T>(distribution : Distribution, matrix_a : &Matrix<T>, matrix_b : &Matrix<T>) -> Self {
+    Layout::new(distribution, matrix_a.len(), matrix_b.len(), matrix_b[0].len())
+  }
+
+  pub fn grid_rows(&self) -> usize {
+    self.distribution.rows()
+  }
+
+  pub fn grid_cols(&self) -> usize {
+    self.distribution.cols()
+  }
+
+  /// Rows and columns of the block of the product the core at (`row`,
+  /// `col`) holds, which may be empty when the grid is larger than the
+  /// matrices
+  pub fn block_c(&self, row : usize, col : usize) -> (usize, usize) {
+    let (rows, cols) = self.distribution.indices(row, col, self.rows, self.cols);
+    (rows.len(), cols.len())
+  }
+
+  /// `Ok` if the distribution splits the columns of A the same way as the
+  /// rows of B, which methods multiplying the blocks they hold need
+  pub fn needs_square_blocks(&self) -> Result<(), ShapeError> {
+    match self.distribution.cyclic_blocks() {
+      Some((block_rows, block_cols)) if block_rows != block_cols => Err(ShapeError::Blocks { block_rows, block_cols }),
+      _ => Ok(()),
+    }
+  }
+
+  /// `Ok` if the grid satisfies `condition`, which `needs` describes
+  pub fn needs(&self, condition : bool, needs : &'static str) -> Result<(), ShapeError> {
+    if condition {
+      Ok(())
+    } else {
+      let layers = self.distribution.layers();
+      Err(ShapeError::Grid { rows : self.grid_rows() / layers, cols : self.grid_cols(), layers, needs })
+    }
+  }
+}
 
-pub trait CommMethod<T: Multiplicable + Sendable, CoreType : Core<Matrix<T>>> {
-  fn outer_setup_a(rows : usize, cols : usize, matrix_a : &Matrix<T>) -> VecDeque<Matrix<T>> {
-    VecDeque::from(get_submatrices(rows, cols, matrix_a))
+pub trait CommMethod<T: Semiring + Sendable, CoreType : Core<Matrix<T>>> {
+  /// Distribution the matrices are laid out with when the caller asks for
+  /// `distribution`, or why the method cannot lay them out on its grid
+  fn distribution(distribution : &Distribution) -> Result<Distribution, ShapeError> {
+    Ok(distribution.clone())
+  }
+  /// Checks the method can run on `layout` before any core starts. Most
+  /// methods shift blocks around a square grid and multiply the blocks
+  /// they hold, which is all the default asks for.
+  fn check(layout : &Layout) -> Result<(), ShapeError> {
+    layout.needs(layout.grid_rows() == layout.grid_cols(), "needs a square grid")?;
+    layout.needs_square_blocks()
+  }
+  fn outer_setup_a(distribution : &Distribution, matrix_a : &Matrix<T>) -> VecDeque<Matrix<T>> {
+    VecDeque::from(distribution.scatter(matrix_a))
   }
-  fn outer_setup_b(rows : usize, cols : usize, matrix_b : &Matrix<T>,) -> VecDeque<Matrix<T>> {
-    VecDeque::from(get_submatrices(rows, cols, matrix_b))
+  fn outer_setup_b(distribution : &Distribution, matrix_b : &Matrix<T>) -> VecDeque<Matrix<T>> {
+    VecDeque::from(distribution.scatter(matrix_b))
   }
-  fn outer_setup_c(rows : usize, cols : usize, matrix_c : &Matrix<T>,) -> VecDeque<Matrix<T>> {
-    VecDeque::from(get_submatrices(rows, cols, matrix_c))
+  fn outer_setup_c(distribution : &Distribution, matrix_c : &Matrix<T>) -> VecDeque<Matrix<T>> {
+    VecDeque::from(distribution.scatter(matrix_c))
   }
   fn inner_setup_a(a : Matrix<T>, _ : &mut CoreType) 
     -> Matrix<T> {
@@ -24,18 +94,21 @@ pub trait CommMethod<T: Multiplicable + Sendable, CoreType : Core<Matrix<T>>> {
     b
   }
   fn matrix_mult(_ : Matrix<T>, _ : Matrix<T>, 
-                                   _ : Matrix<T>, _ : usize, _ : &mut CoreType) -> Matrix<T>;
+                                   _ : Matrix<T>, _ : &Layout, _ : &mut CoreType) -> Matrix<T>;
 }
 
 pub struct Hash;
 
 impl<T, CoreType>  CommMethod<T, CoreType> for Hash 
-  where T : Sendable + Multiplicable,
+  where T : Sendable + Semiring,
         CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
 
   fn matrix_mult(matrix_a : Matrix<T>, matrix_b : Matrix<T>, 
-                                     mut matrix_c : Matrix<T>, iterations : usize,
+                                     matrix_c : Matrix<T>, layout : &Layout,
                                      core_info : &mut CoreType) -> Matrix<T> {
+    let iterations = layout.grid_rows();
+    core_info.allocate(matrix_a.payload_size() + matrix_b.payload_size() + matrix_c.payload_size());
+    let mut product = DenseMatrix::from(matrix_c);
     for iter in 0..iterations {
       if core_info.col() == iter {
         core_info.send(matrix_a.clone(), &TaurusOption::ROW);
@@ -45,9 +118,15 @@ impl<T, CoreType>  CommMethod<T, CoreType> for Hash
       }
       let received_a = core_info.recv(&TaurusOption::ROW);
       let received_b = core_info.recv(&TaurusOption::COL);
+      // Broadcast copies are held alongside the local blocks
+      let received = received_a.payload_size() + received_b.payload_size();
+      core_info.allocate(received);
 
-      matrix_c = serial_matmul(&received_a, &received_b, &matrix_c);
+      mul_add_blocks(&mut product, &received_a, &received_b);
+      core_info.free(received);
     }
+    let matrix_c = Matrix::from(product);
+    core_info.free(matrix_a.payload_size() + matrix_b.payload_size() + matrix_c.payload_size());
     return matrix_c;
   }
 }
@@ -55,23 +134,32 @@ impl<T, CoreType>  CommMethod<T, CoreType> for Hash
 pub struct FoxOtto;
 
 impl<T, CoreType>  CommMethod<T, CoreType> for FoxOtto 
-  where T : Sendable + Multiplicable,
+  where T : Sendable + Semiring,
         CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
   fn matrix_mult(matrix_a : Matrix<T>, matrix_b : Matrix<T>, 
-                                     mut matrix_c : Matrix<T>, iterations : usize,
+                                     matrix_c : Matrix<T>, layout : &Layout,
                                      core_info : &mut CoreType) -> Matrix<T> {
+    let iterations = layout.grid_rows();
+    core_info.allocate(matrix_a.payload_size() + matrix_b.payload_size() + matrix_c.payload_size());
+    let mut product = DenseMatrix::from(matrix_c);
     let mut received_b = matrix_b;
     for iter in 0..iterations {
       if iter == (( iterations + core_info.col() - core_info.row()) % iterations ) {
         core_info.send(matrix_a.clone(), &TaurusOption::ROW);
       }
       let received_a = core_info.recv(&TaurusOption::ROW);
+      core_info.allocate(received_a.payload_size());
       
-      matrix_c = serial_matmul(&received_a, &received_b, &matrix_c);
+      mul_add_blocks(&mut product, &received_a, &received_b);
+      core_info.free(received_a.payload_size());
       
+      core_info.free(received_b.payload_size());
       core_info.send(received_b, &TaurusOption::UP);
       received_b = core_info.recv(&TaurusOption::DOWN);
+      core_info.allocate(received_b.payload_size());
     }
+    let matrix_c = Matrix::from(product);
+    core_info.free(matrix_a.payload_size() + received_b.payload_size() + matrix_c.payload_size());
     return matrix_c;
   }
 }
@@ -79,22 +167,30 @@ impl<T, CoreType>  CommMethod<T, CoreType> for FoxOtto
 pub struct PipeFoxOtto;
 
 impl<T, CoreType>  CommMethod<T, CoreType> for PipeFoxOtto 
-  where T : Sendable + Multiplicable,
+  where T : Sendable + Semiring,
         CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
   fn matrix_mult(matrix_a : Matrix<T>, matrix_b : Matrix<T>, 
-                                     mut matrix_c : Matrix<T>, iterations : usize,
+                                     matrix_c : Matrix<T>, layout : &Layout,
                                      core_info : &mut CoreType) -> Matrix<T> {
+    let iterations = layout.grid_rows();
+    core_info.allocate(matrix_a.payload_size() + matrix_b.payload_size() + matrix_c.payload_size());
+    let mut product = DenseMatrix::from(matrix_c);
     let mut received_b = matrix_b;
     for iter in 0..iterations {
+      core_info.free(received_b.payload_size());
       core_info.send(received_b, &TaurusOption::UP);
       if iter == (( iterations + core_info.col() - core_info.row() - 1)  % iterations ) {
         core_info.send(matrix_a.clone(), &TaurusOption::ROW);
       }
       received_b = core_info.recv(&TaurusOption::DOWN);
       let received_a = core_info.recv(&TaurusOption::ROW);
+      core_info.allocate(received_b.payload_size() + received_a.payload_size());
 
-      matrix_c = serial_matmul(&received_a, &received_b, &matrix_c);
+      mul_add_blocks(&mut product, &received_a, &received_b);
+      core_info.free(received_a.payload_size());
     }
+    let matrix_c = Matrix::from(product);
+    core_info.free(matrix_a.payload_size() + received_b.payload_size() + matrix_c.payload_size());
     return matrix_c;
   }
 }
@@ -102,32 +198,14 @@ impl<T, CoreType>  CommMethod<T, CoreType> for PipeFoxOtto
 pub struct Cannon;
 
 impl<T, CoreType>  CommMethod<T, CoreType> for Cannon 
-  where T : Sendable + Multiplicable,
+  where T : Sendable + Semiring,
         CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
-  fn outer_setup_a(rows : usize, cols : usize, matrix_a : &Matrix<T>,) -> VecDeque<Matrix<T>> {
-    let submatrices_a = VecDeque::from(get_submatrices(rows, cols, matrix_a));
-    let indices : Vec<usize> = (0..rows)
-      .flat_map(|row| (0..cols)
-                .map(|col| row * cols +((cols + col - row) % cols))
-                .collect::<Vec<_>>())
-      .collect();
-    let mut result = indices.iter().map(|_| Vec::new()).collect::<VecDeque<Matrix<T>>>();
-    submatrices_a.into_iter().zip(indices.iter()).map(|(m, &index)| result[index] = m).count();
-
-    result
+  fn outer_setup_a(distribution : &Distribution, matrix_a : &Matrix<T>) -> VecDeque<Matrix<T>> {
+    VecDeque::from(Distribution::skewed(distribution.clone(), Skew::Left).scatter(matrix_a))
   }
 
-  fn outer_setup_b(rows : usize, cols : usize, matrix_b : &Matrix<T>,) -> VecDeque<Matrix<T>> {
-    let submatrices_b = VecDeque::from(get_submatrices(rows, cols, matrix_b));
-    let indices : Vec<usize> = (0..rows)
-      .flat_map(|row| (0..cols)
-                .map(|col| ((rows + row - col) % rows) * cols + col)
-                .collect::<Vec<_>>())
-      .collect();
-    let mut result = indices.iter().map(|_| Vec::new()).collect::<VecDeque<Matrix<T>>>();
-    submatrices_b.into_iter().zip(indices.iter()).map(|(m, &index)| result[index] = m).count();
-
-    result
+  fn outer_setup_b(distribution : &Distribution, matrix_b : &Matrix<T>) -> VecDeque<Matrix<T>> {
+    VecDeque::from(Distribution::skewed(distribution.clone(), Skew::Up).scatter(matrix_b))
   }
 
   fn inner_setup_a (a : Matrix<T>, core_info : &mut CoreType) 
@@ -151,19 +229,488 @@ impl<T, CoreType>  CommMethod<T, CoreType> for Cannon
   }
 
   fn matrix_mult(matrix_a : Matrix<T>, matrix_b : Matrix<T>, 
-                                     mut matrix_c : Matrix<T>, iterations : usize,
+                                     matrix_c : Matrix<T>, layout : &Layout,
                                      core_info : &mut CoreType) -> Matrix<T> {
+    let iterations = layout.grid_rows();
+    core_info.allocate(matrix_a.payload_size() + matrix_b.payload_size() + matrix_c.payload_size());
+    let mut product = DenseMatrix::from(matrix_c);
     let mut received_a = matrix_a;
     let mut received_b = matrix_b;
 
     for _ in 0..iterations {
-      matrix_c = serial_matmul(&received_a, &received_b, &matrix_c);
+      mul_add_blocks(&mut product, &received_a, &received_b);
       
+      core_info.free(received_a.payload_size() + received_b.payload_size());
       core_info.send(received_a, &TaurusOption::LEFT);
       core_info.send(received_b, &TaurusOption::UP);
       received_a = core_info.recv(&TaurusOption::RIGHT);
       received_b = core_info.recv(&TaurusOption::DOWN);
+      core_info.allocate(received_a.payload_size() + received_b.payload_size());
     }
+    let matrix_c = Matrix::from(product);
+    core_info.free(received_a.payload_size() + received_b.payload_size() + matrix_c.payload_size());
     return matrix_c;
   }
 }
+
+/// SUMMA, which walks the inner dimension in panels `PANEL` wide. For every
+/// panel the cores holding its columns of A broadcast them along their rows
+/// and the cores holding its rows of B broadcast them down their columns, then
+/// every core adds the product of the two panels to its block of C. The panel
+/// width does not depend on the block size, and the grid may be rectangular.
+pub struct Summa<const PANEL : usize = 32>;
+
+impl<T, CoreType, const PANEL : usize>  CommMethod<T, CoreType> for Summa<PANEL> 
+  where T : Sendable + Semiring,
+        CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
+  fn check(_ : &Layout) -> Result<(), ShapeError> {
+    Ok(())
+  }
+
+  fn matrix_mult(matrix_a : Matrix<T>, matrix_b : Matrix<T>, 
+                                     matrix_c : Matrix<T>, layout : &Layout,
+                                     core_info : &mut CoreType) -> Matrix<T> {
+    assert!(PANEL > 0, "panel width must be positive");
+    let (row, col) = (core_info.row(), core_info.col());
+    // Inner indices of the columns of A held along this row of the grid, and
+    // of the rows of B held along this column
+    let inner_a : Vec<Vec<usize>> = (0..layout.grid_cols())
+      .map(|j| layout.distribution.indices(row, j, layout.rows, layout.inner).1)
+      .collect();
+    let inner_b : Vec<Vec<usize>> = (0..layout.grid_rows())
+      .map(|i| layout.distribution.indices(i, col, layout.inner, layout.cols).0)
+      .collect();
+
+    core_info.allocate(matrix_a.payload_size() + matrix_b.payload_size() + matrix_c.payload_size());
+    let mut product = DenseMatrix::from(matrix_c);
+    for start in (0..layout.inner).step_by(PANEL) {
+      let end = (start + PANEL).min(layout.inner);
+
+      let mut columns : Vec<(usize, Vec<T>)> = Vec::new();
+      for (owner, indices) in inner_a.iter().enumerate() {
+        let positions = panel_positions(indices, start, end);
+        if positions.is_empty() {
+          continue;
+        }
+        if col == owner {
+          let piece = matrix_a.iter()
+            .map(|r| positions.iter().map(|&p| r[p].clone()).collect())
+            .collect();
+          core_info.send(piece, &TaurusOption::ROW);
+        }
+        let piece = core_info.recv(&TaurusOption::ROW);
+        core_info.allocate(piece.payload_size());
+        for (n, &p) in positions.iter().enumerate() {
+          columns.push((indices[p], piece.iter().map(|r| r[n].clone()).collect()));
+        }
+      }
+
+      let mut rows : Vec<(usize, Vec<T>)> = Vec::new();
+      for (owner, indices) in inner_b.iter().enumerate() {
+        let positions = panel_positions(indices, start, end);
+        if positions.is_empty() {
+          continue;
+        }
+        if row == owner {
+          let piece = positions.iter().map(|&p| matrix_b[p].clone()).collect();
+          core_info.send(piece, &TaurusOption::COL);
+        }
+        let piece = core_info.recv(&TaurusOption::COL);
+        core_info.allocate(piece.payload_size());
+        for (&p, r) in positions.iter().zip(piece) {
+          rows.push((indices[p], r));
+        }
+      }
+
+      // Line the columns of A up with the rows of B they multiply
+      columns.sort_by_key(|(k, _)| *k);
+      rows.sort_by_key(|(k, _)| *k);
+      let panel_a : Matrix<T> = (0..matrix_a.len())
+        .map(|i| columns.iter().map(|(_, column)| column[i].clone()).collect())
+        .collect();
+      let panel_b : Matrix<T> = rows.into_iter().map(|(_, r)| r).collect();
+
+      mul_add_blocks(&mut product, &panel_a, &panel_b);
+      core_info.free(panel_a.payload_size() + panel_b.payload_size());
+    }
+    let matrix_c = Matrix::from(product);
+    core_info.free(matrix_a.payload_size() + matrix_b.payload_size() + matrix_c.payload_size());
+    matrix_c
+  }
+}
+
+/// Positions in `indices` of the inner indices in `start..end`
+fn panel_positions(indices : &[usize], start : usize, end : usize) -> Vec<usize> {
+  indices.iter().enumerate()
+    .filter(|(_, &k)| start <= k && k < end)
+    .map(|(p, _)| p)
+    .collect()
+}
+
+/// Reads a distribution over a `c * q` x `q` grid as one over the first of
+/// `c` stacked `q` x `q` layers. Distributions which are already stacked are
+/// kept, and grids whose rows are not a multiple of their columns cannot be
+/// read as layers.
+fn on_layers(distribution : &Distribution) -> Result<Distribution, ShapeError> {
+  let (rows, cols) = (distribution.rows(), distribution.cols());
+  if distribution.layers() > 1 {
+    Ok(distribution.clone())
+  } else if rows.is_multiple_of(cols) {
+    Ok(distribution.on_first_layer(rows / cols))
+  } else {
+    Err(ShapeError::Grid { rows, cols, layers : 1, needs : "needs square layers stacked along the rows" })
+  }
+}
+
+/// Layer of the core and its position inside the layer as
+/// `(layer, row, col)`, checking that the layers are square
+fn stack_position<T, CoreType>(layout : &Layout, core_info : &CoreType) -> (usize, usize, usize)
+where T : Sendable,
+      CoreType : Core<T> {
+  let side = layout.grid_cols();
+  assert_eq!(layout.grid_rows(), side * layout.distribution.layers(), "layers must be square");
+  (core_info.row() / side, core_info.row() % side, core_info.col())
+}
+
+/// Copies the blocks of layer 0 to every layer along the fibres
+fn replicate<T, CoreType>(matrix_a : Matrix<T>, matrix_b : Matrix<T>, layer : usize,
+                          core_info : &mut CoreType) -> (Matrix<T>, Matrix<T>)
+where T : Sendable,
+      CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
+  core_info.free(matrix_a.payload_size() + matrix_b.payload_size());
+  if layer == 0 {
+    core_info.send(matrix_a, &TaurusOption::FIBRE);
+    core_info.send(matrix_b, &TaurusOption::FIBRE);
+  }
+  let a = core_info.recv(&TaurusOption::FIBRE);
+  let b = core_info.recv(&TaurusOption::FIBRE);
+  core_info.allocate(a.payload_size() + b.payload_size());
+  (a, b)
+}
+
+/// Shifts `matrix` `steps` times in `send` on every core of a row or column
+fn shift<T, CoreType>(matrix : Matrix<T>, steps : usize, send : &TaurusOption, recv : &TaurusOption,
+                      core_info : &mut CoreType) -> Matrix<T>
+where T : Sendable,
+      CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
+  let mut temp = matrix;
+  for _ in 0..steps {
+    core_info.free(temp.payload_size());
+    core_info.send(temp, send);
+    temp = core_info.recv(recv);
+    core_info.allocate(temp.payload_size());
+  }
+  temp
+}
+
+/// Sums the partial results of every layer onto layer 0, passing the running
+/// sum up the fibre from the last layer. The other layers return an empty
+/// block.
+fn reduce_layers<T, CoreType>(partial : Matrix<T>, layer : usize, layers : usize,
+                              core_info : &mut CoreType) -> Matrix<T>
+where T : Sendable + Semiring,
+      CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
+  let mut sum = partial;
+  if layer + 1 < layers {
+    let later = core_info.recv(&TaurusOption::BELOW);
+    let received = later.payload_size();
+    core_info.allocate(received);
+    sum = elementwise(&sum, &later, T::add);
+    core_info.free(received);
+  }
+  if layer > 0 {
+    core_info.free(sum.payload_size());
+    core_info.send(sum, &TaurusOption::ABOVE);
+    return Vec::new();
+  }
+  sum
+}
+
+/// Dekel-Nassimi-Sahni 3D multiplication on `q` stacked `q` x `q` layers,
+/// so the processor needs `q * q` rows and `q` columns and a network stacked
+/// into `q` layers. Layer 0 holds the matrices and copies them along the
+/// fibres, layer `l` multiplies column `l` of A by row `l` of B using one row
+/// and one column broadcast, and the products are summed back onto layer 0.
+pub struct Dns;
+
+impl<T, CoreType>  CommMethod<T, CoreType> for Dns 
+  where T : Sendable + Semiring,
+        CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
+  fn distribution(distribution : &Distribution) -> Result<Distribution, ShapeError> {
+    on_layers(distribution)
+  }
+
+  fn check(layout : &Layout) -> Result<(), ShapeError> {
+    let layers = layout.distribution.layers();
+    layout.needs(layout.grid_rows() == layers * layout.grid_cols() && layers == layout.grid_cols(),
+                 "needs as many square layers as the side of a layer")?;
+    layout.needs_square_blocks()
+  }
+
+  fn matrix_mult(matrix_a : Matrix<T>, matrix_b : Matrix<T>, 
+                                     matrix_c : Matrix<T>, layout : &Layout,
+                                     core_info : &mut CoreType) -> Matrix<T> {
+    let (layer, row, col) = stack_position::<Matrix<T>, _>(layout, core_info);
+    let layers = layout.distribution.layers();
+    assert_eq!(layers, layout.grid_cols(), "DNS needs as many layers as the side of a layer");
+
+    core_info.allocate(matrix_a.payload_size() + matrix_b.payload_size() + matrix_c.payload_size());
+    let (a, b) = replicate(matrix_a, matrix_b, layer, core_info);
+
+    core_info.free(a.payload_size() + b.payload_size());
+    if col == layer {
+      core_info.send(a, &TaurusOption::ROW);
+    }
+    if row == layer {
+      core_info.send(b, &TaurusOption::COL);
+    }
+    let received_a = core_info.recv(&TaurusOption::ROW);
+    let received_b = core_info.recv(&TaurusOption::COL);
+    core_info.allocate(received_a.payload_size() + received_b.payload_size());
+
+    let partial = if layer == 0 {
+      serial_matmul(&received_a, &received_b, &matrix_c)
+    } else {
+      let (rows, cols) = layout.block_c(row, col);
+      let zero = T::zero_matrix(rows, cols);
+      core_info.allocate(zero.payload_size());
+      serial_matmul(&received_a, &received_b, &zero)
+    };
+    core_info.free(received_a.payload_size() + received_b.payload_size());
+
+    let matrix_c = reduce_layers(partial, layer, layers, core_info);
+    core_info.free(matrix_c.payload_size());
+    matrix_c
+  }
+}
+
+/// 2.5D Cannon on `c` stacked `q` x `q` layers, so the processor needs
+/// `c * q` rows and `q` columns and a network stacked into `c` layers, with
+/// `c <= q`. Layer 0 copies the matrices to every layer, each layer runs its
+/// own share of the `q` steps of Cannon's algorithm, and the products are
+/// summed back onto layer 0. Keeping `c` copies cuts the words each core
+/// sends by a factor of `sqrt(c)` over 2D Cannon.
+pub struct Cannon25D;
+
+impl<T, CoreType>  CommMethod<T, CoreType> for Cannon25D 
+  where T : Sendable + Semiring,
+        CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
+  fn distribution(distribution : &Distribution) -> Result<Distribution, ShapeError> {
+    on_layers(distribution)
+  }
+
+  fn check(layout : &Layout) -> Result<(), ShapeError> {
+    let layers = layout.distribution.layers();
+    layout.needs(layout.grid_rows() == layers * layout.grid_cols() && layers <= layout.grid_cols(),
+                 "needs square layers, at most as many as the side of a layer")?;
+    layout.needs_square_blocks()
+  }
+
+  fn matrix_mult(matrix_a : Matrix<T>, matrix_b : Matrix<T>, 
+                                     matrix_c : Matrix<T>, layout : &Layout,
+                                     core_info : &mut CoreType) -> Matrix<T> {
+    let (layer, row, col) = stack_position::<Matrix<T>, _>(layout, core_info);
+    let (layers, side) = (layout.distribution.layers(), layout.grid_cols());
+    assert!(layers <= side, "2.5D Cannon needs at most as many layers as the side of a layer");
+
+    core_info.allocate(matrix_a.payload_size() + matrix_b.payload_size() + matrix_c.payload_size());
+    let (a, b) = replicate(matrix_a, matrix_b, layer, core_info);
+
+    // Layer `l` runs the steps `first..last` of Cannon's algorithm, starting
+    // from blocks skewed that much further
+    let first = layer * side / layers;
+    let last = (layer + 1) * side / layers;
+    let mut a = shift(a, (row + first) % side, &TaurusOption::LEFT, &TaurusOption::RIGHT, core_info);
+    let mut b = shift(b, (col + first) % side, &TaurusOption::UP, &TaurusOption::DOWN, core_info);
+
+    let mut partial = DenseMatrix::from(if layer == 0 {
+      matrix_c
+    } else {
+      let (rows, cols) = layout.block_c(row, col);
+      let zero = T::zero_matrix(rows, cols);
+      core_info.allocate(zero.payload_size());
+      zero
+    });
+    for step in first..last {
+      mul_add_blocks(&mut partial, &a, &b);
+      if step + 1 < last {
+        a = shift(a, 1, &TaurusOption::LEFT, &TaurusOption::RIGHT, core_info);
+        b = shift(b, 1, &TaurusOption::UP, &TaurusOption::DOWN, core_info);
+      }
+    }
+    core_info.free(a.payload_size() + b.payload_size());
+
+    let matrix_c = reduce_layers(partial.into(), layer, layers, core_info);
+    core_info.free(matrix_c.payload_size());
+    matrix_c
+  }
+}
+
+/// Strassen-Winograd on top of another method `F`. The matrices are laid out
+/// with `Distribution::Quadrants`, so the sums of quadrants are local to every
+/// core and each of the `LEVELS` levels trades one of 8 multiplications of
+/// quadrants for 15 local additions. The 7 products of a level are multiplied
+/// one after another on the whole grid, by `F` at the last level. Every
+/// dimension of the matrices must be a multiple of `2^LEVELS`.
+pub struct Strassen<F, const LEVELS : usize = 1>(PhantomData<F>);
+
+impl<T, CoreType, F, const LEVELS : usize>  CommMethod<T, CoreType> for Strassen<F, LEVELS> 
+  where T : Sendable + Ring,
+        CoreType : Core<Matrix<T>>,
+        F : CommMethod<T, CoreType> {
+  fn distribution(distribution : &Distribution) -> Result<Distribution, ShapeError> {
+    Ok(Distribution::quadrants(F::distribution(distribution)?, LEVELS))
+  }
+
+  fn check(layout : &Layout) -> Result<(), ShapeError> {
+    let blocks = 1 << LEVELS;
+    if let Some(&dimension) = [layout.rows, layout.inner, layout.cols].iter().find(|d| !d.is_multiple_of(blocks)) {
+      return Err(ShapeError::Indivisible { dimension, blocks });
+    }
+    let Distribution::Quadrants(distribution, _) = &layout.distribution else {
+      return F::check(layout);
+    };
+    F::check(&Layout::new((**distribution).clone(), layout.rows / blocks, layout.inner / blocks, layout.cols / blocks))
+  }
+
+  fn outer_setup_a(distribution : &Distribution, matrix_a : &Matrix<T>) -> VecDeque<Matrix<T>> {
+    setup_blocks(distribution, matrix_a, F::outer_setup_a)
+  }
+
+  fn outer_setup_b(distribution : &Distribution, matrix_b : &Matrix<T>) -> VecDeque<Matrix<T>> {
+    setup_blocks(distribution, matrix_b, F::outer_setup_b)
+  }
+
+  fn outer_setup_c(distribution : &Distribution, matrix_c : &Matrix<T>) -> VecDeque<Matrix<T>> {
+    setup_blocks(distribution, matrix_c, F::outer_setup_c)
+  }
+
+  fn inner_setup_a(a : Matrix<T>, core_info : &mut CoreType) -> Matrix<T> {
+    let blocks = split_blocks(&a, 1 << LEVELS);
+    tile(blocks.into_iter().map(|block| F::inner_setup_a(block, core_info)).collect(), 1 << LEVELS)
+  }
+
+  fn inner_setup_b(b : Matrix<T>, core_info : &mut CoreType) -> Matrix<T> {
+    let blocks = split_blocks(&b, 1 << LEVELS);
+    tile(blocks.into_iter().map(|block| F::inner_setup_b(block, core_info)).collect(), 1 << LEVELS)
+  }
+
+  fn matrix_mult(matrix_a : Matrix<T>, matrix_b : Matrix<T>, 
+                                     matrix_c : Matrix<T>, layout : &Layout,
+                                     core_info : &mut CoreType) -> Matrix<T> {
+    strassen_winograd::<T, CoreType, F>(matrix_a, matrix_b, matrix_c, layout, core_info)
+  }
+}
+
+/// One level of Strassen-Winograd on the local quadrants, recursing until
+/// the layout is no longer split into quadrants
+fn strassen_winograd<T, CoreType, F>(matrix_a : Matrix<T>, matrix_b : Matrix<T>, matrix_c : Matrix<T>,
+                                     layout : &Layout, core_info : &mut CoreType) -> Matrix<T>
+where T : Sendable + Ring,
+      CoreType : Core<Matrix<T>>,
+      F : CommMethod<T, CoreType> {
+  let Distribution::Quadrants(distribution, levels) = &layout.distribution else {
+    return F::matrix_mult(matrix_a, matrix_b, matrix_c, layout, core_info);
+  };
+  let quadrant = Layout::new(Distribution::quadrants((**distribution).clone(), levels - 1),
+                             layout.rows / 2, layout.inner / 2, layout.cols / 2);
+  let [a11, a12, a21, a22] = quadrants(&matrix_a);
+  let [b11, b12, b21, b22] = quadrants(&matrix_b);
+  let [c11, c12, c21, c22] = quadrants(&matrix_c);
+
+  let s1 = elementwise(&a21, &a22, T::add);
+  let s2 = elementwise(&s1, &a11, T::subtract);
+  let s3 = elementwise(&a11, &a21, T::subtract);
+  let s4 = elementwise(&a12, &s2, T::subtract);
+  let t1 = elementwise(&b12, &b11, T::subtract);
+  let t2 = elementwise(&b22, &t1, T::subtract);
+  let t3 = elementwise(&b22, &b12, T::subtract);
+  let t4 = elementwise(&t2, &b21, T::subtract);
+  let sums = [&s1, &s2, &s3, &s4, &t1, &t2, &t3, &t4].iter().map(|sum| sum.payload_size()).sum::<usize>();
+  core_info.allocate(sums);
+
+  let (rows, cols) = quadrant.block_c(core_info.row(), core_info.col());
+  let mut product = |x : Matrix<T>, y : Matrix<T>| {
+    let zero = T::zero_matrix(rows, cols);
+    strassen_winograd::<T, CoreType, F>(x, y, zero, &quadrant, core_info)
+  };
+  let p1 = product(a11, b11);
+  let p2 = product(a12, b21);
+  let p3 = product(s4, b22);
+  let p4 = product(a22, t4);
+  let p5 = product(s1, t1);
+  let p6 = product(s2, t2);
+  let p7 = product(s3, t3);
+  let products = [&p1, &p2, &p3, &p4, &p5, &p6, &p7].iter().map(|p| p.payload_size()).sum::<usize>();
+  core_info.allocate(products);
+
+  let u1 = elementwise(&p1, &p2, T::add);
+  let u2 = elementwise(&p1, &p6, T::add);
+  let u3 = elementwise(&u2, &p7, T::add);
+  let u4 = elementwise(&u2, &p5, T::add);
+  let u5 = elementwise(&u4, &p3, T::add);
+  let u6 = elementwise(&u3, &p4, T::subtract);
+  let u7 = elementwise(&u3, &p5, T::add);
+  core_info.free(sums + products);
+
+  tile(vec![elementwise(&c11, &u1, T::add), elementwise(&c12, &u5, T::add),
+            elementwise(&c21, &u6, T::add), elementwise(&c22, &u7, T::add)], 2)
+}
+
+/// Lays out each of the blocks of `matrix` with `setup` and tiles the pieces
+/// of every core, see `Distribution::Quadrants`
+fn setup_blocks<T : Clone>(distribution : &Distribution, matrix : &Matrix<T>,
+                           setup : fn(&Distribution, &Matrix<T>) -> VecDeque<Matrix<T>>) -> VecDeque<Matrix<T>> {
+  let Distribution::Quadrants(inner, levels) = distribution else {
+    return setup(distribution, matrix);
+  };
+  let blocks = 1 << levels;
+  let mut pieces : Vec<VecDeque<Matrix<T>>> = split_blocks(matrix, blocks).iter()
+    .map(|block| setup(inner, block))
+    .collect();
+  (0..distribution.rows() * distribution.cols())
+    .map(|_| tile(pieces.iter_mut().map(|piece| piece.pop_front().unwrap()).collect(), blocks))
+    .collect()
+}
+
+/// Cuts `matrix` into `blocks` x `blocks` equal blocks in row-major order
+fn split_blocks<T : Clone>(matrix : &Matrix<T>, blocks : usize) -> Vec<Matrix<T>> {
+  let block_rows = matrix.len() / blocks;
+  let block_cols = matrix.first().map_or(0, Vec::len) / blocks;
+  (0..blocks * blocks)
+    .map(|block| matrix[block / blocks * block_rows..(block / blocks + 1) * block_rows].iter()
+      .map(|row| row[block % blocks * block_cols..(block % blocks + 1) * block_cols].to_vec())
+      .collect())
+    .collect()
+}
+
+/// The four quadrants of `matrix` as `[11, 12, 21, 22]`
+fn quadrants<T : Clone>(matrix : &Matrix<T>) -> [Matrix<T>; 4] {
+  split_blocks(matrix, 2).try_into().unwrap_or_else(|_| unreachable!("a matrix has four quadrants"))
+}
+
+/// Joins `blocks` x `blocks` blocks given in row-major order, the inverse of
+/// `split_blocks`
+fn tile<T>(blocks : Vec<Matrix<T>>, count : usize) -> Matrix<T> {
+  let mut tiled = Vec::new();
+  let mut blocks = blocks.into_iter();
+  for _ in 0..count {
+    let row_of_blocks : Vec<Matrix<T>> = blocks.by_ref().take(count).collect();
+    let height = row_of_blocks[0].len();
+    let mut rows : Vec<Vec<T>> = (0..height).map(|_| Vec::new()).collect();
+    for block in row_of_blocks {
+      for (row, block_row) in rows.iter_mut().zip(block) {
+        row.extend(block_row);
+      }
+    }
+    tiled.extend(rows);
+  }
+  tiled
+}
+
+/// Applies `op` to the matching elements of two matrices of the same shape
+fn elementwise<T : Clone>(x : &Matrix<T>, y : &Matrix<T>, op : fn(T, T) -> T) -> Matrix<T> {
+  x.iter().zip(y)
+    .map(|(x_row, y_row)| x_row.iter().zip(y_row).map(|(a, b)| op(a.clone(), b.clone())).collect())
+    .collect()
+}
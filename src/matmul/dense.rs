@@ -0,0 +1,124 @@
+use crate::processor::probe::ops::record_operations;
+use crate::types::Matrix;
+use super::Semiring;
+
+/// Side of the square tiles of B the kernel works through, small enough that
+/// a tile of B and the matching parts of the rows of C stay in cache
+const TILE : usize = 64;
+
+/// Matrix stored contiguously in row-major order, which the local kernel
+/// multiplies without chasing a pointer per row
+#[derive(Clone, Debug, PartialEq)]
+pub struct DenseMatrix<T> {
+  rows : usize,
+  cols : usize,
+  data : Vec<T>,
+}
+
+impl<T> DenseMatrix<T> {
+  /// A `rows` x `cols` matrix from its elements in row-major order
+  pub fn new(rows : usize, cols : usize, data : Vec<T>) -> Self {
+    assert_eq!(data.len(), rows * cols, "a {rows} x {cols} matrix needs {} elements", rows * cols);
+    DenseMatrix { rows, cols, data }
+  }
+
+  pub fn rows(&self) -> usize {
+    self.rows
+  }
+
+  pub fn cols(&self) -> usize {
+    self.cols
+  }
+
+  pub fn get(&self, row : usize, col : usize) -> &T {
+    &self.data[row * self.cols + col]
+  }
+
+  /// Row `row` as a slice
+  pub fn row(&self, row : usize) -> &[T] {
+    &self.data[row * self.cols..(row + 1) * self.cols]
+  }
+
+  pub fn row_mut(&mut self, row : usize) -> &mut [T] {
+    &mut self.data[row * self.cols..(row + 1) * self.cols]
+  }
+}
+
+impl<T : Semiring> DenseMatrix<T> {
+  /// Adds `a` times `b` to `self` in place. The loops run in i-k-j order
+  /// over tiles of B, so the innermost loop streams along a row of B and a
+  /// row of C, and the terms of every element are still added in order of
+  /// `k` for semirings which break ties.
+  pub fn mul_add(&mut self, a : &DenseMatrix<T>, b : &DenseMatrix<T>) {
+    self.mul_add_by((a.rows, a.cols), b.rows, |i, k| a.get(i, k), |k| b.row(k));
+  }
+
+  /// Adds `a` times `b` to `self` in place like `mul_add`, reading the rows
+  /// of the blocks where they are instead of copying them
+  pub fn mul_add_rows(&mut self, a : &Matrix<T>, b : &Matrix<T>) {
+    let a_cols = a.first().map_or(0, Vec::len);
+    self.mul_add_by((a.len(), a_cols), b.len(), |i, k| &a[i][k], |k| &b[k]);
+  }
+
+  /// The kernel of `mul_add`, for an `a_rows` x `a_cols` matrix A whose
+  /// elements `a_at` gives and a matrix B with `b_rows` rows `b_row` gives
+  fn mul_add_by<'m>(&mut self, (a_rows, a_cols) : (usize, usize), b_rows : usize,
+                    a_at : impl Fn(usize, usize) -> &'m T, b_row : impl Fn(usize) -> &'m [T])
+  where T : 'm {
+    assert_eq!(a_rows, self.rows, "A has {} rows but C has {}", a_rows, self.rows);
+    // Empty blocks contribute no terms, whatever shape they claim to have
+    if a_rows == 0 || a_cols == 0 {
+      return;
+    }
+    assert_eq!(a_cols, b_rows, "A has {} columns but B has {} rows", a_cols, b_rows);
+    let b_cols = b_row(0).len();
+    assert_eq!(b_cols, self.cols, "B has {} columns but C has {}", b_cols, self.cols);
+    record_operations(a_rows * a_cols * b_cols);
+
+    let cols = self.cols;
+    for k_start in (0..a_cols).step_by(TILE) {
+      let k_end = (k_start + TILE).min(a_cols);
+      for j_start in (0..cols).step_by(TILE) {
+        let j_end = (j_start + TILE).min(cols);
+        for i in 0..a_rows {
+          let c_row = &mut self.data[i * cols + j_start..i * cols + j_end];
+          for k in k_start..k_end {
+            let a_ik = a_at(i, k);
+            for (c, b_kj) in c_row.iter_mut().zip(&b_row(k)[j_start..j_end]) {
+              T::mul_add(c, a_ik, b_kj);
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+impl<T> From<Matrix<T>> for DenseMatrix<T> {
+  /// Flattens `matrix`, taking the number of columns from its first row
+  fn from(matrix : Matrix<T>) -> Self {
+    let rows = matrix.len();
+    let cols = matrix.first().map_or(0, Vec::len);
+    let data : Vec<T> = matrix.into_iter().flatten().collect();
+    DenseMatrix::new(rows, cols, data)
+  }
+}
+
+impl<T : Clone> From<&Matrix<T>> for DenseMatrix<T> {
+  fn from(matrix : &Matrix<T>) -> Self {
+    let rows = matrix.len();
+    let cols = matrix.first().map_or(0, Vec::len);
+    let data : Vec<T> = matrix.iter().flatten().cloned().collect();
+    DenseMatrix::new(rows, cols, data)
+  }
+}
+
+impl<T> From<DenseMatrix<T>> for Matrix<T> {
+  fn from(matrix : DenseMatrix<T>) -> Self {
+    if matrix.cols == 0 {
+      return (0..matrix.rows).map(|_| Vec::new()).collect();
+    }
+    let mut data = matrix.data.into_iter();
+    (0..matrix.rows).map(|_| data.by_ref().take(matrix.cols).collect()).collect()
+  }
+}
@@ -0,0 +1,236 @@
+use crate::processor::{taurus::TaurusOption, Core, Runner, Grid};
+use crate::processor::distribution::{Distribution, Redistribution};
+use crate::broadcast::Sendable;
+use crate::types::Matrix;
+
+use super::Semiring;
+use super::comm_method::{CommMethod, Layout};
+
+/// A matrix which lives as blocks on the cores of a processor.
+///
+/// Operations run on the processor, with each core working on the block it
+/// holds, so results can be chained without gathering the matrix back to
+/// the host in between. The processor hands its cores back after every
+/// operation and can be reused for the next one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistMatrix<T> {
+  rows : usize,
+  cols : usize,
+  distribution : Distribution,
+  blocks : Grid<Matrix<T>>,
+}
+
+impl<T> DistMatrix<T>
+where T : Semiring + Sendable + 'static {
+  /// Splits `matrix` into blocks laid out according to `distribution`
+  pub fn scatter(matrix : &Matrix<T>, distribution : Distribution) -> Self {
+    let mut blocks = Grid::new(distribution.rows(), distribution.cols());
+    for (index, block) in distribution.scatter(matrix).into_iter().enumerate() {
+      blocks.insert(index / distribution.cols(), index % distribution.cols(), block);
+    }
+    DistMatrix { rows : matrix.len(), cols : matrix[0].len(), distribution, blocks }
+  }
+
+  /// Reassembles the blocks into a single matrix on the host
+  pub fn gather(&self) -> Matrix<T> {
+    let mut matrix = T::zero_matrix(self.rows, self.cols);
+    for (row, col, block) in self.blocks.iter() {
+      self.distribution.gather(row, col, block, &mut matrix);
+    }
+    matrix
+  }
+
+  pub fn rows(&self) -> usize {
+    self.rows
+  }
+
+  pub fn cols(&self) -> usize {
+    self.cols
+  }
+
+  pub fn distribution(&self) -> &Distribution {
+    &self.distribution
+  }
+
+  /// Block held by the core at (`row`, `col`)
+  pub fn block(&self, row : usize, col : usize) -> Option<&Matrix<T>> {
+    self.blocks.get(row, col)
+  }
+
+  /// Panics unless the blocks are laid out over the grid of `processor`
+  fn check_processor<CoreType, R>(&self, processor : &R)
+  where CoreType : Core<Matrix<T>>,
+        R : Runner<Matrix<T>, CoreType> {
+    assert_eq!((self.distribution.rows(), self.distribution.cols()), (processor.rows(), processor.cols()),
+               "the matrix is distributed over a different grid from the processor");
+  }
+
+  fn with_blocks(&self, rows : usize, cols : usize, blocks : Grid<Matrix<T>>) -> Self {
+    DistMatrix { rows, cols, distribution : self.distribution.clone(), blocks }
+  }
+
+  /// Applies `f` to every element, each core mapping its own block
+  pub fn map<F, CoreType, R>(&self, processor : &mut R, f : F) -> Self
+  where F : Fn(T) -> T + Clone + Send + 'static,
+        CoreType : Core<Matrix<T>>,
+        R : Runner<Matrix<T>, CoreType> {
+    self.check_processor(processor);
+    let mut blocks = self.blocks.clone();
+    processor.run_all(|row, col| {
+      let block = blocks.take(row, col).unwrap();
+      let f = f.clone();
+      move |_ : &mut CoreType| {
+        block.into_iter().map(|r| r.into_iter().map(&f).collect()).collect()
+      }
+    });
+    self.with_blocks(self.rows, self.cols, processor.collect_grid())
+  }
+
+  /// Combines two matrices with the same shape and distribution elementwise
+  pub fn zip_with<F, CoreType, R>(&self, other : &Self, processor : &mut R, f : F) -> Self
+  where F : Fn(T, T) -> T + Clone + Send + 'static,
+        CoreType : Core<Matrix<T>>,
+        R : Runner<Matrix<T>, CoreType> {
+    assert_eq!((self.rows, self.cols), (other.rows, other.cols), "matrices must have the same shape");
+    assert_eq!(self.distribution, other.distribution, "matrices must have the same distribution");
+    self.check_processor(processor);
+
+    let mut blocks_a = self.blocks.clone();
+    let mut blocks_b = other.blocks.clone();
+    processor.run_all(|row, col| {
+      let a = blocks_a.take(row, col).unwrap();
+      let b = blocks_b.take(row, col).unwrap();
+      let f = f.clone();
+      move |_ : &mut CoreType| {
+        a.into_iter().zip(b)
+          .map(|(row_a, row_b)| row_a.into_iter().zip(row_b).map(|(x, y)| f(x, y)).collect())
+          .collect()
+      }
+    });
+    self.with_blocks(self.rows, self.cols, processor.collect_grid())
+  }
+
+  /// Elementwise `Semiring::add` of `self` and `other`, the shortest of two
+  /// paths over `MinPlus`
+  pub fn add<CoreType, R>(&self, other : &Self, processor : &mut R) -> Self
+  where CoreType : Core<Matrix<T>>,
+        R : Runner<Matrix<T>, CoreType> {
+    self.zip_with(other, processor, T::add)
+  }
+
+  /// Multiplies `self` by `other` using the communication method `F`.
+  ///
+  /// The blocks are aligned on the processor with `F::inner_setup_a` and
+  /// `F::inner_setup_b`, so methods such as `Cannon` skew the blocks over the
+  /// network rather than on the host.
+  pub fn multiply<F, CoreType, R>(&self, other : &Self, processor : &mut R) -> Self
+  where F : CommMethod<T, CoreType>,
+        CoreType : Core<Matrix<T>>,
+        R : Runner<Matrix<T>, CoreType> {
+    assert_eq!(self.cols, other.rows, "inner dimensions must match");
+    assert_eq!(self.distribution, other.distribution, "matrices must have the same distribution");
+    self.check_processor(processor);
+
+    let mut blocks_a = self.blocks.clone();
+    let mut blocks_b = other.blocks.clone();
+    let layout = Layout::new(self.distribution.clone(), self.rows, self.cols, other.cols);
+    F::check(&layout).unwrap_or_else(|error| panic!("{error}"));
+    processor.run_all(|row, col| {
+      let a = blocks_a.take(row, col).unwrap();
+      let b = blocks_b.take(row, col).unwrap();
+      let layout = layout.clone();
+      move |core_info : &mut CoreType| {
+        let (rows, cols) = layout.block_c(row, col);
+        let c = T::zero_matrix(rows, cols);
+        let a = F::inner_setup_a(a, core_info);
+        let b = F::inner_setup_b(b, core_info);
+        F::matrix_mult(a, b, c, &layout, core_info)
+      }
+    });
+    self.with_blocks(self.rows, other.cols, processor.collect_grid())
+  }
+
+  fn redistribution(&self, rows : usize, cols : usize, to : &Distribution) -> Redistribution {
+    Redistribution::new(self.distribution.clone(), to.clone(), self.rows, self.cols, rows, cols)
+  }
+
+  fn with_grid(&self, to : Distribution, mut results : Grid<Matrix<T>>) -> Self {
+    let mut blocks = Grid::new(to.rows(), to.cols());
+    for row in 0..to.rows() {
+      for col in 0..to.cols() {
+        blocks.insert(row, col, results.take(row, col).unwrap());
+      }
+    }
+    DistMatrix { rows : self.rows, cols : self.cols, distribution : to, blocks }
+  }
+
+  /// Moves the matrix to the layout `to` by point-to-point messages between
+  /// the cores. Both layouts must fit inside the grid of the processor. On a
+  /// `ProbeProcessor` the cost of the move is recorded in its debug stats.
+  pub fn redistribute<CoreType, R>(&self, processor : &mut R, to : Distribution) -> Self
+  where CoreType : Core<Matrix<T>, ChannelOption = TaurusOption>,
+        R : Runner<Matrix<T>, CoreType> {
+    let redistribution = self.redistribution(processor.rows(), processor.cols(), &to);
+    let mut blocks = self.blocks.clone();
+    processor.run_all(|row, col| {
+      let block = blocks.take(row, col).unwrap_or_default();
+      let redistribution = redistribution.clone();
+      move |core_info : &mut CoreType| redistribution.run(block, core_info)
+    });
+    self.with_grid(to, processor.collect_grid())
+  }
+
+  /// Transposes the matrix on the processor.
+  ///
+  /// Every row of the grid first gathers its blocks onto the diagonal core,
+  /// which then broadcasts them down its column so that core (`row`, `col`)
+  /// receives block (`col`, `row`) and transposes it locally. The grid must be
+  /// square and the distribution must split both axes the same way.
+  pub fn transpose<CoreType, R>(&self, processor : &mut R) -> Self
+  where CoreType : Core<Matrix<T>, ChannelOption = TaurusOption>,
+        R : Runner<Matrix<T>, CoreType> {
+    let size = self.distribution.rows();
+    assert_eq!(size, self.distribution.cols(), "transpose requires a square grid");
+    if let Distribution::BlockCyclic { block_rows, block_cols, .. } = self.distribution {
+      assert_eq!(block_rows, block_cols, "transpose requires square blocks");
+    }
+
+    let mut blocks = self.blocks.clone();
+    processor.run_all(|row, col| {
+      let block = blocks.take(row, col).unwrap();
+      move |core_info : &mut CoreType| {
+        let mut row_blocks = Vec::new();
+        for iter in 0..size {
+          if col == iter {
+            core_info.send(block.clone(), &TaurusOption::ROW);
+          }
+          row_blocks.push(core_info.recv(&TaurusOption::ROW));
+        }
+
+        let mut received = Vec::new();
+        for (iter, row_block) in row_blocks.into_iter().enumerate() {
+          if row == col {
+            core_info.send(row_block, &TaurusOption::COL);
+          }
+          let block = core_info.recv(&TaurusOption::COL);
+          if iter == row {
+            received = block;
+          }
+        }
+        transpose_block(received)
+      }
+    });
+    DistMatrix { rows : self.cols, cols : self.rows, distribution : self.distribution.clone(), blocks : processor.collect_grid() }
+  }
+}
+
+fn transpose_block<T>(block : Matrix<T>) -> Matrix<T> {
+  let cols = block.first().map_or(0, |row| row.len());
+  let mut columns : Vec<Vec<T>> = (0..cols).map(|_| Vec::with_capacity(block.len())).collect();
+  for row in block {
+    for (column, element) in columns.iter_mut().zip(row) {
+      column.push(element);
+    }
+  }
+  columns
+}
@@ -1,252 +1,527 @@
-use crate::processor::{ProbeProcessor, Core};
-use crate::processor::probe::Prober;
-use crate::processor::{taurus::TaurusCore, get_submatrices_dim, Processor};
+use std::{fmt, marker::PhantomData};
+use crate::processor::{Core, Runner};
+use crate::processor::{taurus::{TaurusCore, TaurusOption}, distribution::Distribution, Processor, Grid};
 use crate::broadcast::Sendable;
 use crate::types::Matrix;
 
 pub mod comm_method;
-use comm_method::CommMethod;
+pub mod dense;
+pub mod dist;
+pub mod sparse;
+pub mod verify;
+use comm_method::{CommMethod, Layout};
+use dense::DenseMatrix;
+use sparse::CsrMatrix;
+use verify::{Mismatch, find_mismatch};
+
+/// Elements matrices can be multiplied over, with `add` summing the terms
+/// `mul` produces. Besides ordinary arithmetic this covers the path problems
+/// of `crate::types::semiring`, where a product of adjacency matrices
+/// combines the paths through every intermediate node.
+pub trait Semiring : Clone {
+  /// Identity of `add`, which `mul` by anything gives back
+  fn zero() -> Self;
+  /// Identity of `mul`
+  fn one() -> Self;
+  /// Sums two terms. `a` comes from earlier terms of the sum than `b`, so
+  /// semirings which break ties keep the earlier term.
+  fn add(a : Self, b : Self) -> Self;
+  fn mul(a : Self, b : Self) -> Self;
+  /// Adds the product of `a` and `b` to `acc` in place, as `add(acc, mul(a,
+  /// b))`. Kernels call this in their innermost loop, where the clones are
+  /// free for `Copy` types; others can override it to avoid them.
+  fn mul_add(acc : &mut Self, a : &Self, b : &Self) {
+    *acc = Self::add(acc.clone(), Self::mul(a.clone(), b.clone()));
+  }
+  /// Whether the element is `zero`, which adds nothing to a sum and so can
+  /// be left out of sparse blocks. Types which cannot tell are never zero,
+  /// so their blocks are always dense.
+  fn is_zero(&self) -> bool {
+    false
+  }
+  /// Whether `add(a, a)` is `a`, in which case repeatedly squaring an
+  /// adjacency matrix with `one` on the diagonal converges to its closure
+  const IDEMPOTENT : bool = false;
+
+  /// Matrix of `zero`, where a sum of products with no terms starts
+  fn zero_matrix(rows : usize, cols : usize) -> Matrix<Self> {
+    vec![vec![Self::zero(); cols]; rows]
+  }
+
+  /// Identity matrix of `mul`, with `one` on the diagonal and `zero`
+  /// elsewhere
+  fn identity_matrix(size : usize) -> Matrix<Self> {
+    (0..size).map(|i| (0..size).map(|j| if i == j { Self::one() } else { Self::zero() }).collect()).collect()
+  }
+}
+
+/// `Semiring`s where `add` can be undone, as needed by Strassen's algorithm
+pub trait Ring : Semiring {
+  /// The `x` for which `add(b, x)` is `a`
+  fn subtract(a : Self, b : Self) -> Self;
+}
 
-pub trait Multiplicable { 
-  fn initial_c (a : &Matrix<Self>, b : &Matrix<Self>) -> Matrix<Self> where Self: Sized;
-  fn neutral_matrix (rows : usize, cols : usize) -> Matrix<Self> where Self: Sized;
-  fn singleton_matrix(a : Self, b : Self, c : Self) -> Self;
+/// Matrices a multiplication cannot be run on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShapeError {
+  /// Matrix `matrix` has no rows or no columns
+  Empty { matrix : char },
+  /// Row `row` of matrix `matrix` has `len` elements where its first has
+  /// `cols`
+  Ragged { matrix : char, row : usize, len : usize, cols : usize },
+  /// A has `cols_a` columns but B has `rows_b` rows
+  Mismatch { cols_a : usize, rows_b : usize },
+  /// Powers and closures need a square matrix
+  NotSquare { rows : usize, cols : usize },
+  /// A method which splits every dimension into `blocks` parts got one
+  /// which is not a multiple of it
+  Indivisible { dimension : usize, blocks : usize },
+  /// The `rows` x `cols` grid of `layers` layers is not one the method
+  /// runs on, which `needs` describes
+  Grid { rows : usize, cols : usize, layers : usize, needs : &'static str },
+  /// The distribution is over a `rows` x `cols` grid but the processor is
+  /// `grid_rows` x `grid_cols`
+  Distribution { rows : usize, cols : usize, grid_rows : usize, grid_cols : usize },
+  /// The method pairs the columns of A with the rows of B block by block,
+  /// but the distribution deals them out in `block_rows` x `block_cols`
+  /// blocks
+  Blocks { block_rows : usize, block_cols : usize },
 }
 
-pub fn serial_matmul<T : Multiplicable + Clone>(matrix_a : &Matrix<T>,
+impl fmt::Display for ShapeError {
+  fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ShapeError::Empty { matrix } => write!(f, "Matrix {matrix} is empty"),
+      ShapeError::Ragged { matrix, row, len, cols } =>
+        write!(f, "Row {row} of matrix {matrix} has {len} elements but row 0 has {cols}"),
+      ShapeError::Mismatch { cols_a, rows_b } =>
+        write!(f, "Cannot multiply A with {cols_a} columns by B with {rows_b} rows"),
+      ShapeError::NotSquare { rows, cols } => write!(f, "A {rows} x {cols} matrix is not square"),
+      ShapeError::Indivisible { dimension, blocks } =>
+        write!(f, "A dimension of {dimension} cannot be split into {blocks} blocks"),
+      ShapeError::Grid { rows, cols, layers : 1, needs } =>
+        write!(f, "The method {needs}, but the grid is {rows} x {cols}"),
+      ShapeError::Grid { rows, cols, layers, needs } =>
+        write!(f, "The method {needs}, but the grid is {layers} layers of {rows} x {cols}"),
+      ShapeError::Distribution { rows, cols, grid_rows, grid_cols } =>
+        write!(f, "The distribution is over a {rows} x {cols} grid but the processor is {grid_rows} x {grid_cols}"),
+      ShapeError::Blocks { block_rows, block_cols } =>
+        write!(f, "The method needs square blocks, but the distribution deals out {block_rows} x {block_cols} blocks"),
+    }
+  }
+}
+
+impl std::error::Error for ShapeError {}
+
+/// Why a `MatMul` did not return a product
+#[derive(Clone, Debug, PartialEq)]
+pub enum MatMulError<T> {
+  Shape(ShapeError),
+  /// The product differs from the one computed on the host, which is only
+  /// checked with `MatMul::with_verification`
+  Mismatch(Mismatch<T>),
+  /// A closure was asked for over a semiring which is not idempotent, where
+  /// the squarings need not converge
+  NotIdempotent,
+}
+
+impl<T> From<ShapeError> for MatMulError<T> {
+  fn from(error : ShapeError) -> Self {
+    MatMulError::Shape(error)
+  }
+}
+
+impl<T : fmt::Debug> fmt::Display for MatMulError<T> {
+  fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      MatMulError::Shape(error) => error.fmt(f),
+      MatMulError::Mismatch(mismatch) => mismatch.fmt(f),
+      MatMulError::NotIdempotent => write!(f, "The closure only converges over an idempotent semiring"),
+    }
+  }
+}
+
+impl<T : fmt::Debug> std::error::Error for MatMulError<T> {}
+
+/// Rows and columns of `matrix`, named `name` in errors
+fn shape<T>(name : char, matrix : &Matrix<T>) -> Result<(usize, usize), ShapeError> {
+  let cols = matrix.first().map_or(0, Vec::len);
+  if cols == 0 {
+    return Err(ShapeError::Empty { matrix : name });
+  }
+  match matrix.iter().position(|row| row.len() != cols) {
+    Some(row) => Err(ShapeError::Ragged { matrix : name, row, len : matrix[row].len(), cols }),
+    None => Ok((matrix.len(), cols)),
+  }
+}
+
+/// Checks `matrix_a` and `matrix_b` can be multiplied, returning the shape of
+/// the product
+pub fn check_shapes<T>(matrix_a : &Matrix<T>, matrix_b : &Matrix<T>) -> Result<(usize, usize), ShapeError> {
+  let (rows_a, cols_a) = shape('A', matrix_a)?;
+  let (rows_b, cols_b) = shape('B', matrix_b)?;
+  if cols_a != rows_b {
+    return Err(ShapeError::Mismatch { cols_a, rows_b });
+  }
+  Ok((rows_a, cols_b))
+}
+
+/// Checks `matrix` is square, returning its size
+fn check_square<T>(matrix : &Matrix<T>) -> Result<usize, ShapeError> {
+  let (rows, cols) = shape('A', matrix)?;
+  if rows != cols {
+    return Err(ShapeError::NotSquare { rows, cols });
+  }
+  Ok(rows)
+}
+
+/// `matrix_c` plus the product of `matrix_a` and `matrix_b`, see
+/// `mul_add_blocks`. Blocks of small matrices on large grids may be empty, in
+/// which case the result is `matrix_c`.
+pub fn serial_matmul<T : Semiring>(matrix_a : &Matrix<T>,
                                                        matrix_b : &Matrix<T>,
                                                        matrix_c : &Matrix<T>)
 -> Matrix<T>{
-  let rows_a = matrix_a.len();
-  let cols_b = matrix_b[0].len();
-  let cols_a = matrix_a[0].len();
+  let mut product = DenseMatrix::from(matrix_c);
+  mul_add_blocks(&mut product, matrix_a, matrix_b);
+  product.into()
+}
 
-  (0..rows_a)
-    .map(|i| 
-      (0..cols_b)
-        .map(|j| 
-          (0..cols_a)
-            .fold(matrix_c[i][j].clone(), |acc, k| T::singleton_matrix(matrix_a[i][k].clone(), matrix_b[k][j].clone(), acc))
-        ).collect::<Vec<T>>()
-    ).collect::<Matrix<T>>()
+/// Adds the product of `matrix_a` and `matrix_b` to `product` in place, by
+/// the cache-blocked kernel of `DenseMatrix` reading the blocks where they
+/// are, or by the kernels of `CsrMatrix` when `matrix_a` is sparse. The
+/// methods hold their block of C as a `DenseMatrix` while they run, so only
+/// the blocks they receive are read.
+pub fn mul_add_blocks<T : Semiring>(product : &mut DenseMatrix<T>, matrix_a : &Matrix<T>, matrix_b : &Matrix<T>) {
+  match CsrMatrix::sparse(matrix_a) {
+    Some(sparse_a) => match CsrMatrix::sparse(matrix_b) {
+      Some(sparse_b) => sparse_a.mul_add_sparse(&sparse_b, product),
+      None => sparse_a.mul_add_dense(&DenseMatrix::from(matrix_b), product),
+    },
+    None => product.mul_add_rows(matrix_a, matrix_b),
+  }
 }
 
-pub struct MatMul<'a,T> 
-where T : Multiplicable + Sendable + 'static {
-  processor : &'a mut Processor<(usize, usize, Matrix<T>),Matrix<T>, TaurusCore<Matrix<T>>>
+/// Runs multiplications with a `CommMethod` on a grid of cores. `R` is the
+/// processor, a `Processor` of `TaurusCore`s unless given, and `C` the type
+/// of core the methods run on, which for a `ProbeProcessor` is the prober
+/// wrapping its cores.
+pub struct MatMul<'a, T, C = TaurusCore<Matrix<T>>, R = Processor<Matrix<T>, Matrix<T>, C>> 
+where T : Semiring + Sendable + PartialEq + 'static,
+      C : Core<Matrix<T>>,
+      R : Runner<Matrix<T>, C> {
+  processor : &'a mut R,
+  distribution : Distribution,
+  verify : bool,
+  phantom : PhantomData<fn(&mut C) -> T>,
 }
 
-impl<'a,T> MatMul<'a,T> 
-where T : Multiplicable + Sendable + 'static {
-  pub fn new(processor : &'a mut Processor<(usize, usize, Matrix<T>),Matrix<T>, TaurusCore<Matrix<T>>>) -> Self {
+impl<'a, T, C, R> MatMul<'a, T, C, R> 
+where T : Semiring + Sendable + PartialEq + 'static,
+      C : Core<Matrix<T>>,
+      R : Runner<Matrix<T>, C> {
+  pub fn new(processor : &'a mut R) -> Self {
+    let distribution = Distribution::Block(processor.speeds().clone());
     MatMul {
-      processor 
+      processor,
+      distribution,
+      verify : false,
+      phantom : PhantomData,
     }
   }
 
-  fn collect_c(&self, core_results : &Vec<(usize, usize, Matrix<T>)>,
-               matrix_c : &mut Matrix<T>) {
-    let m_rows = matrix_c.len();
-    let m_cols = matrix_c[0].len();
-    let submatrices_dim = get_submatrices_dim(self.processor.rows, self.processor.cols, m_rows, m_cols);
+  /// Lays the matrices out over the cores using `distribution` instead of the
+  /// default speed-balanced block distribution
+  pub fn with_distribution(mut self, distribution : Distribution) -> Self {
+    self.distribution = distribution;
+    self
+  }
 
+  /// Recomputes every product with `serial_matmul` on the host and reports
+  /// the first element which differs as a `MatMulError::Mismatch`, to catch
+  /// mistakes in new `CommMethod`s. The host product costs as much as the
+  /// whole simulation, so this is meant for development runs.
+  pub fn with_verification(mut self) -> Self {
+    self.verify = true;
+    self
+  }
+
+  /// `matrix_c` if it matches the product `expected` computes, when
+  /// verifying
+  fn verified(&self, distribution : &Distribution, matrix_c : Matrix<T>,
+              expected : impl FnOnce() -> Matrix<T>) -> Result<Matrix<T>, MatMulError<T>> {
+    if !self.verify {
+      return Ok(matrix_c);
+    }
+    match find_mismatch(distribution, &expected(), &matrix_c) {
+      Some(mismatch) => Err(MatMulError::Mismatch(mismatch)),
+      None => Ok(matrix_c),
+    }
+  }
+
+  /// Layout `F` multiplies `matrix_a` by `matrix_b` with, checking the
+  /// distribution covers the processor before `F` lays it out
+  fn layout<F>(&self, matrix_a : &Matrix<T>, matrix_b : &Matrix<T>) -> Result<Layout, ShapeError>
+    where F : CommMethod<T,C> {
+    let (rows, cols) = (self.distribution.rows(), self.distribution.cols());
+    if (rows, cols) != (self.processor.rows(), self.processor.cols()) {
+      return Err(ShapeError::Distribution { rows, cols, grid_rows : self.processor.rows(), grid_cols : self.processor.cols() });
+    }
+    let layout = Layout::of(F::distribution(&self.distribution)?, matrix_a, matrix_b);
+    F::check(&layout)?;
+    Ok(layout)
+  }
+
+  fn collect_c(distribution : &Distribution, core_results : &Grid<Matrix<T>>,
+               matrix_c : &mut Matrix<T>) {
     // Assign the final values to the W and P matrix
-    for (i, j, c)  in core_results {
-      let index = i * self.processor.cols + j;
-      let submatrix_dim = submatrices_dim[index];
-      for i in 0..submatrix_dim.height {
-        for j in 0..submatrix_dim.width {
-          matrix_c[submatrix_dim.start_row+i][submatrix_dim.start_col+j] = c[i][j].clone();
-        }
-      }
+    for (i, j, c)  in core_results.iter() {
+      distribution.gather(i, j, c, matrix_c);
     }
   }
   
+  /// Product of `matrix_a` and `matrix_b`, which may have any compatible
+  /// shapes. Panics if they do not; see `try_parallel_mult`.
   pub fn parallel_mult<F>  (&mut self, matrix_a : Matrix<T>, matrix_b : Matrix<T>)
     -> Matrix<T> 
-    where F : CommMethod<T,TaurusCore<Matrix<T>>>  {
-
-    let rows = self.processor.rows;
-    let cols = self.processor.cols;
-    let mut submatrices_a = F::outer_setup_a(rows, cols, &matrix_a);
-    let mut submatrices_b = F::outer_setup_b(rows, cols, &matrix_b);
-    let mut matrix_c = T::initial_c(&matrix_a, &matrix_b);
-    let mut submatrices_c = F::outer_setup_c(rows, cols, &matrix_c);
-
-    for i in 0..rows {
-      for j in 0..cols {
-        // Sender for returning the results
-        let iterations = rows;
-
-        // Assign each threads matrix component
-        let a = submatrices_a.pop_front().unwrap();
-        let b = submatrices_b.pop_front().unwrap();
-        let c = submatrices_c.pop_front().unwrap();
-
-        let core_function = move |core_info: &mut TaurusCore<Vec<Vec<T>>>| {
-          let c = F::matrix_mult(a, b, c, iterations, core_info);
-          (i,j,c)
-        };
-
-        self.processor.run_core(core_function);
+    where F : CommMethod<T,C>  {
+    self.try_parallel_mult::<F>(matrix_a, matrix_b).unwrap_or_else(|error| panic!("{error}"))
+  }
+
+  /// Product of `matrix_a` and `matrix_b`, or why they cannot be multiplied
+  /// with `F` on this processor
+  pub fn try_parallel_mult<F>  (&mut self, matrix_a : Matrix<T>, matrix_b : Matrix<T>)
+    -> Result<Matrix<T>, MatMulError<T>> 
+    where F : CommMethod<T,C>  {
+    let (rows, cols) = check_shapes(&matrix_a, &matrix_b)?;
+    let layout = self.layout::<F>(&matrix_a, &matrix_b)?;
+    let distribution = layout.distribution.clone();
+
+    let mut submatrices_a = F::outer_setup_a(&distribution, &matrix_a);
+    let mut submatrices_b = F::outer_setup_b(&distribution, &matrix_b);
+    let mut matrix_c = T::zero_matrix(rows, cols);
+    let mut submatrices_c = F::outer_setup_c(&distribution, &matrix_c);
+
+    self.processor.run_all(|_, _| {
+      // Assign each threads matrix component
+      let a = submatrices_a.pop_front().unwrap();
+      let b = submatrices_b.pop_front().unwrap();
+      let c = submatrices_c.pop_front().unwrap();
+      let layout = layout.clone();
+
+      move |core_info: &mut C| {
+        F::matrix_mult(a, b, c, &layout, core_info)
       }
-    }
+    });
 
-    let core_results = self.processor.collect_results();
-    self.collect_c(&core_results, &mut matrix_c);
-    matrix_c
+    let core_results = self.processor.collect_grid();
+    Self::collect_c(&distribution, &core_results, &mut matrix_c);
+    self.verified(&distribution, matrix_c, || serial_matmul(&matrix_a, &matrix_b, &T::zero_matrix(rows, cols)))
   }   
 
+  /// Panics if `matrix_a` is not square; see `try_parallel_square`
   pub fn parallel_square<F> (&mut self, matrix_a : Matrix<T>, outer_iterations : usize)
     -> Matrix<T> 
-    where F : CommMethod<T,TaurusCore<Matrix<T>>> {
-
-    let mut submatrices_a = F::outer_setup_a(self.processor.rows, self.processor.cols, &matrix_a);
-    let mut submatrices_b = F::outer_setup_b(self.processor.rows, self.processor.cols, &matrix_a);
-    let mut matrix_c = T::initial_c(&matrix_a, &matrix_a);
-    let mut submatrices_c = F::outer_setup_c(self.processor.rows, self.processor.cols, &matrix_c);
-
-    for i in 0..self.processor.rows {
-      for j in 0..self.processor.cols {
-        // Sender for returning the results
-        let inner_iterations = self.processor.rows;
-
-        // Assign each threads matrix component
-        let mut a = submatrices_a.pop_front().unwrap();
-        let mut b = submatrices_b.pop_front().unwrap();
-        let mut c = submatrices_c.pop_front().unwrap();
-
-        let core_function = move |core_info: &mut TaurusCore<Vec<Vec<T>>>| {
-          for _ in 0..outer_iterations{
-            c = F::matrix_mult(a, b, c, inner_iterations, core_info);
-            a = F::inner_setup_a(c.clone(), core_info);
-            b = F::inner_setup_b(c.clone(), core_info);
-          }
-          (i,j,c)
-        };
-
-        self.processor.run_core(core_function);
+    where F : CommMethod<T,C> {
+    self.try_parallel_square::<F>(matrix_a, outer_iterations).unwrap_or_else(|error| panic!("{error}"))
+  }
+
+  pub fn try_parallel_square<F> (&mut self, matrix_a : Matrix<T>, outer_iterations : usize)
+    -> Result<Matrix<T>, MatMulError<T>> 
+    where F : CommMethod<T,C> {
+    let size = check_square(&matrix_a)?;
+    let layout = self.layout::<F>(&matrix_a, &matrix_a)?;
+    let distribution = layout.distribution.clone();
+
+    let mut submatrices_a = F::outer_setup_a(&distribution, &matrix_a);
+    let mut submatrices_b = F::outer_setup_b(&distribution, &matrix_a);
+    let mut matrix_c = T::zero_matrix(size, size);
+    let mut submatrices_c = F::outer_setup_c(&distribution, &matrix_c);
+
+    self.processor.run_all(|_, _| {
+      // Assign each threads matrix component
+      let mut a = submatrices_a.pop_front().unwrap();
+      let mut b = submatrices_b.pop_front().unwrap();
+      let mut c = submatrices_c.pop_front().unwrap();
+      let layout = layout.clone();
+
+      move |core_info: &mut C| {
+        for _ in 0..outer_iterations{
+          c = F::matrix_mult(a, b, c, &layout, core_info);
+          a = F::inner_setup_a(c.clone(), core_info);
+          b = F::inner_setup_b(c.clone(), core_info);
+        }
+        c
       }
-    }
+    });
 
-    let core_results = self.processor.collect_results();
-    self.collect_c(&core_results, &mut matrix_c);
-    matrix_c
+    let core_results = self.processor.collect_grid();
+    Self::collect_c(&distribution, &core_results, &mut matrix_c);
+    self.verified(&distribution, matrix_c, || verify::serial_square(&matrix_a, outer_iterations))
   }
-}
 
-pub struct ProbeMatMul<'a, T, D, U, CoreType> 
-where T : Multiplicable + Sendable + 'static,
-      D : Sendable + 'static,
-      U : Sendable + 'static,
-      CoreType : Core<U> + Send,
-      {
-  processor : &'a mut ProbeProcessor<D, (usize, usize, Matrix<T>), U, CoreType>
-}
+  /// `matrix_a` to the power `power` by square-and-multiply, which takes
+  /// about log2(`power`) squarings and one more multiplication for every
+  /// other bit set in `power`. Panics if `matrix_a` is not square; see
+  /// `try_parallel_pow`.
+  pub fn parallel_pow<F> (&mut self, matrix_a : Matrix<T>, power : usize)
+    -> Matrix<T> 
+    where F : CommMethod<T,C> {
+    self.try_parallel_pow::<F>(matrix_a, power).unwrap_or_else(|error| panic!("{error}"))
+  }
 
-impl<'a, T, D, U, CoreType> ProbeMatMul<'a, T, D, U, CoreType> 
-where T : Multiplicable + Sendable + 'static,
-      D : Sendable + 'static,
-      U : Sendable + 'static,
-      CoreType : Core<U> + Send + 'static,
-      {
-  pub fn new(processor : &'a mut ProbeProcessor<D,(usize, usize, Matrix<T>),U, CoreType>) -> Self {
-    ProbeMatMul {
-      processor 
+  pub fn try_parallel_pow<F> (&mut self, matrix_a : Matrix<T>, power : usize)
+    -> Result<Matrix<T>, MatMulError<T>> 
+    where F : CommMethod<T,C> {
+    let size = check_square(&matrix_a)?;
+    if power == 0 {
+      return Ok(T::identity_matrix(size));
     }
-  }
+    let layout = self.layout::<F>(&matrix_a, &matrix_a)?;
+    let distribution = layout.distribution.clone();
 
-  fn collect_c(&self, core_results : &Vec<(usize, usize, Matrix<T>)>,
-               matrix_c : &mut Matrix<T>) {
-    let m_rows = matrix_c.len();
-    let m_cols = matrix_c[0].len();
-    let submatrices_dim = get_submatrices_dim(self.processor.rows(), self.processor.cols(), m_rows, m_cols);
+    let mut submatrices_a = F::outer_setup_c(&distribution, &matrix_a);
+    let mut matrix_c = T::zero_matrix(size, size);
+    let mut submatrices_c = F::outer_setup_c(&distribution, &matrix_c);
 
-    // Assign the final values to the W and P matrix
-    for (i, j, c)  in core_results {
-      let index = i * self.processor.cols() + j;
-      let submatrix_dim = submatrices_dim[index];
-      for i in 0..submatrix_dim.height {
-        for j in 0..submatrix_dim.width {
-          matrix_c[submatrix_dim.start_row+i][submatrix_dim.start_col+j] = c[i][j].clone();
-        }
+    self.processor.run_all(|_, _| {
+      let a = submatrices_a.pop_front().unwrap();
+      let zero = submatrices_c.pop_front().unwrap();
+      let layout = layout.clone();
+
+      move |core_info: &mut C| {
+        local_pow::<T, F, _>(a, power, &zero, &layout, core_info)
       }
-    }
+    });
+
+    let core_results = self.processor.collect_grid();
+    Self::collect_c(&distribution, &core_results, &mut matrix_c);
+    self.verified(&distribution, matrix_c, || verify::serial_pow(&matrix_a, power))
   }
-  
-  pub fn parallel_mult<F,P>  (&mut self, matrix_a : Matrix<T>, matrix_b : Matrix<T>)
+
+  /// Closure `I + A + A^2 + ...` of `matrix_a` over an idempotent semiring,
+  /// such as the lengths of all shortest paths. `I + A` is squared until it
+  /// stops changing, which the cores agree on with an all-reduce after every
+  /// squaring, so the number of squarings follows the longest path rather
+  /// than the size of the matrix. Panics if `matrix_a` is not square or the
+  /// semiring is not idempotent; see `try_parallel_closure`.
+  pub fn parallel_closure<F> (&mut self, matrix_a : Matrix<T>)
     -> Matrix<T> 
-    where F : CommMethod<T,P>,
-          P : Prober<D, U, CoreType> + Core<Matrix<T>>{
-
-    let mut submatrices_a = F::outer_setup_a(self.processor.rows(), self.processor.cols(), &matrix_a);
-    let mut submatrices_b = F::outer_setup_b(self.processor.rows(), self.processor.cols(), &matrix_b);
-    let mut matrix_c = T::initial_c(&matrix_a, &matrix_b);
-    let mut submatrices_c = F::outer_setup_c(self.processor.rows(), self.processor.cols(), &matrix_c);
-
-    for i in 0..self.processor.rows() {
-      for j in 0..self.processor.cols() {
-        // Sender for returning the results
-        let iterations = self.processor.rows();
-
-        // Assign each threads matrix component
-        let a = submatrices_a.pop_front().unwrap();
-        let b = submatrices_b.pop_front().unwrap();
-        let c = submatrices_c.pop_front().unwrap();
-
-        let core_function = move |core_info: &mut P| {
-          let c = F::matrix_mult(a, b, c, iterations, core_info);
-          (i,j,c)
-        };
-
-        self.processor.run_core(core_function);
-      }
+    where F : CommMethod<T,C>,
+          C : Core<Matrix<T>, ChannelOption = TaurusOption> {
+    self.try_parallel_closure::<F>(matrix_a).unwrap_or_else(|error| panic!("{error}"))
+  }
+
+  pub fn try_parallel_closure<F> (&mut self, matrix_a : Matrix<T>)
+    -> Result<Matrix<T>, MatMulError<T>> 
+    where F : CommMethod<T,C>,
+          C : Core<Matrix<T>, ChannelOption = TaurusOption> {
+    if !T::IDEMPOTENT {
+      return Err(MatMulError::NotIdempotent);
     }
+    let size = check_square(&matrix_a)?;
+    let identity = T::identity_matrix(size);
+    let matrix_a : Matrix<T> = matrix_a.into_iter().zip(identity)
+      .map(|(row, identity_row)| row.into_iter().zip(identity_row).map(|(x, one)| T::add(x, one)).collect())
+      .collect();
+
+    let layout = self.layout::<F>(&matrix_a, &matrix_a)?;
+    let distribution = layout.distribution.clone();
+
+    let mut submatrices_a = F::outer_setup_c(&distribution, &matrix_a);
+    let mut matrix_c = T::zero_matrix(size, size);
+    let mut submatrices_c = F::outer_setup_c(&distribution, &matrix_c);
+
+    self.processor.run_all(|_, _| {
+      let a = submatrices_a.pop_front().unwrap();
+      let zero = submatrices_c.pop_front().unwrap();
+      let layout = layout.clone();
+
+      move |core_info: &mut C| {
+        local_closure::<T, F, _>(a, &zero, &layout, core_info)
+      }
+    });
 
-    let core_results = self.processor.collect_results();
-    self.collect_c(&core_results, &mut matrix_c);
-    matrix_c
-  }   
+    let core_results = self.processor.collect_grid();
+    Self::collect_c(&distribution, &core_results, &mut matrix_c);
+    self.verified(&distribution, matrix_c, || verify::serial_closure(&matrix_a))
+  }
+}
 
-  pub fn parallel_square<F,P> (&mut self, matrix_a : Matrix<T>, outer_iterations : usize)
-    -> Matrix<T> 
-    where F : CommMethod<T,P>,
-          P : Prober<D, U, CoreType> + Core<Matrix<T>>{
-
-    let mut submatrices_a = F::outer_setup_a(self.processor.rows(), self.processor.cols(), &matrix_a);
-    let mut submatrices_b = F::outer_setup_b(self.processor.rows(), self.processor.cols(), &matrix_a);
-    let mut matrix_c = T::initial_c(&matrix_a, &matrix_a);
-    let mut submatrices_c = F::outer_setup_c(self.processor.rows(), self.processor.cols(), &matrix_c);
-
-    for i in 0..self.processor.rows() {
-      for j in 0..self.processor.cols() {
-        // Sender for returning the results
-        let inner_iterations = self.processor.rows();
-
-        // Assign each threads matrix component
-        let mut a = submatrices_a.pop_front().unwrap();
-        let mut b = submatrices_b.pop_front().unwrap();
-        let mut c = submatrices_c.pop_front().unwrap();
-
-        let core_function = move |core_info: &mut P| {
-          for _ in 0..outer_iterations{
-            c = F::matrix_mult(a, b, c, inner_iterations, core_info);
-            a = F::inner_setup_a(c.clone(), core_info);
-            b = F::inner_setup_b(c.clone(), core_info);
-          }
-          (i,j,c)
-        };
-
-        self.processor.run_core(core_function);
-      }
+/// `x * y` on a core, where both are its blocks of the layout of C and
+/// `zero` is its block of the zero matrix
+fn local_mult<T, F, CoreType>(x : Matrix<T>, y : Matrix<T>, zero : &Matrix<T>, layout : &Layout,
+                              core_info : &mut CoreType) -> Matrix<T>
+where T : Semiring + Sendable,
+      CoreType : Core<Matrix<T>>,
+      F : CommMethod<T, CoreType> {
+  let a = F::inner_setup_a(x, core_info);
+  let b = F::inner_setup_b(y, core_info);
+  F::matrix_mult(a, b, zero.clone(), layout, core_info)
+}
+
+/// The block of `base` to the power `power`, which must be positive
+fn local_pow<T, F, CoreType>(base : Matrix<T>, power : usize, zero : &Matrix<T>, layout : &Layout,
+                             core_info : &mut CoreType) -> Matrix<T>
+where T : Semiring + Sendable,
+      CoreType : Core<Matrix<T>>,
+      F : CommMethod<T, CoreType> {
+  let mut base = base;
+  let mut power = power;
+  let mut result : Option<Matrix<T>> = None;
+  loop {
+    if power & 1 == 1 {
+      result = Some(match result {
+        None => base.clone(),
+        Some(result) => local_mult::<T, F, _>(result, base.clone(), zero, layout, core_info),
+      });
+    }
+    power >>= 1;
+    if power == 0 {
+      return result.unwrap();
     }
+    base = local_mult::<T, F, _>(base.clone(), base, zero, layout, core_info);
+  }
+}
 
-    let core_results = self.processor.collect_results();
-    self.collect_c(&core_results, &mut matrix_c);
-    matrix_c
+/// Squares the block of `I + A` until no block of the grid changes
+fn local_closure<T, F, CoreType>(matrix : Matrix<T>, zero : &Matrix<T>, layout : &Layout,
+                                 core_info : &mut CoreType) -> Matrix<T>
+where T : Semiring + Sendable + PartialEq,
+      CoreType : Core<Matrix<T>, ChannelOption = TaurusOption>,
+      F : CommMethod<T, CoreType> {
+  let mut closure = matrix;
+  loop {
+    let squared = local_mult::<T, F, _>(closure.clone(), closure.clone(), zero, layout, core_info);
+    let changed = squared != closure;
+    closure = squared;
+    if !any_changed(changed, layout, core_info) {
+      return closure;
+    }
+  }
+}
+
+/// Whether `changed` is set on any core of the grid, found by broadcasting
+/// it along the rows, then the columns, then the fibres of a stacked grid.
+/// A set flag travels as a matrix with one empty row, so the messages only
+/// cost latency.
+fn any_changed<T, CoreType>(changed : bool, layout : &Layout, core_info : &mut CoreType) -> bool
+where T : Sendable,
+      CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
+  let layers = layout.distribution.layers();
+  let mut rounds = vec![(TaurusOption::ROW, layout.grid_cols()), (TaurusOption::COL, layout.grid_rows() / layers)];
+  if layers > 1 {
+    rounds.push((TaurusOption::FIBRE, layers));
+  }
+  let mut changed = changed;
+  for (direction, cores) in rounds {
+    core_info.send(if changed { vec![Vec::new()] } else { Vec::new() }, &direction);
+    for _ in 0..cores {
+      changed |= !core_info.recv(&direction).is_empty();
+    }
   }
+  changed
 }
 
-    
 #[cfg(test)]
 mod tests;
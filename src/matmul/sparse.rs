@@ -0,0 +1,158 @@
+use std::mem::size_of;
+
+use crate::broadcast::Sendable;
+use crate::processor::probe::ops::record_operations;
+use crate::types::Matrix;
+use super::Semiring;
+use super::dense::DenseMatrix;
+
+/// Blocks with at most this fraction of elements which are not zero are
+/// multiplied as `CsrMatrix`es, such as the adjacency matrices of road
+/// networks before they have been squared a few times
+pub const SPARSE_DENSITY : f64 = 0.1;
+
+/// Matrix in compressed sparse row form, holding only the elements which
+/// are not `Semiring::zero`. The elements of row `i` are
+/// `values[row_starts[i]..row_starts[i + 1]]`, in increasing order of the
+/// columns `col_indices` gives.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsrMatrix<T> {
+  rows : usize,
+  cols : usize,
+  row_starts : Vec<usize>,
+  col_indices : Vec<usize>,
+  values : Vec<T>,
+}
+
+impl<T> CsrMatrix<T> {
+  pub fn rows(&self) -> usize {
+    self.rows
+  }
+
+  pub fn cols(&self) -> usize {
+    self.cols
+  }
+
+  /// Number of elements held
+  pub fn nonzeros(&self) -> usize {
+    self.values.len()
+  }
+
+  /// Column and value of the elements held in row `row`
+  pub fn row(&self, row : usize) -> impl Iterator<Item = (usize, &T)> {
+    let range = self.row_starts[row]..self.row_starts[row + 1];
+    self.col_indices[range.clone()].iter().copied().zip(&self.values[range])
+  }
+}
+
+impl<T : Semiring> CsrMatrix<T> {
+  /// `matrix` in CSR form if at most `SPARSE_DENSITY` of its elements are
+  /// not zero, giving up as soon as it holds more
+  pub fn sparse(matrix : &Matrix<T>) -> Option<Self> {
+    let cols = matrix.first().map_or(0, Vec::len);
+    if matrix.is_empty() || cols == 0 {
+      return None;
+    }
+    Self::build(matrix, Some((SPARSE_DENSITY * (matrix.len() * cols) as f64) as usize))
+  }
+
+  /// Collects the elements of `matrix` which are not zero, or returns `None`
+  /// once there are more than `limit` of them
+  fn build(matrix : &Matrix<T>, limit : Option<usize>) -> Option<Self> {
+    let cols = matrix.first().map_or(0, Vec::len);
+    let mut row_starts = Vec::with_capacity(matrix.len() + 1);
+    let mut col_indices = Vec::new();
+    let mut values = Vec::new();
+    row_starts.push(0);
+    for row in matrix {
+      for (j, x) in row.iter().enumerate().filter(|(_, x)| !x.is_zero()) {
+        if limit == Some(values.len()) {
+          return None;
+        }
+        col_indices.push(j);
+        values.push(x.clone());
+      }
+      row_starts.push(values.len());
+    }
+    Some(CsrMatrix { rows : matrix.len(), cols, row_starts, col_indices, values })
+  }
+
+  /// Adds `self` times `b` to `c` in place, skipping the zeros of `self`
+  pub fn mul_add_dense(&self, b : &DenseMatrix<T>, c : &mut DenseMatrix<T>) {
+    assert_eq!(self.rows, c.rows(), "A has {} rows but C has {}", self.rows, c.rows());
+    if self.nonzeros() == 0 {
+      return;
+    }
+    assert_eq!(self.cols, b.rows(), "A has {} columns but B has {} rows", self.cols, b.rows());
+    assert_eq!(b.cols(), c.cols(), "B has {} columns but C has {}", b.cols(), c.cols());
+    record_operations(self.nonzeros() * b.cols());
+
+    for i in 0..self.rows {
+      for (k, a_ik) in self.row(i) {
+        for (c_ij, b_kj) in c.row_mut(i).iter_mut().zip(b.row(k)) {
+          T::mul_add(c_ij, a_ik, b_kj);
+        }
+      }
+    }
+  }
+
+  /// Adds `self` times `b` to `c` in place, doing a multiply-add only for
+  /// pairs of elements which are both held
+  pub fn mul_add_sparse(&self, b : &CsrMatrix<T>, c : &mut DenseMatrix<T>) {
+    assert_eq!(self.rows, c.rows(), "A has {} rows but C has {}", self.rows, c.rows());
+    if self.nonzeros() == 0 {
+      return;
+    }
+    assert_eq!(self.cols, b.rows, "A has {} columns but B has {} rows", self.cols, b.rows);
+    assert_eq!(b.cols, c.cols(), "B has {} columns but C has {}", b.cols, c.cols());
+
+    let mut operations = 0;
+    for i in 0..self.rows {
+      let c_row = c.row_mut(i);
+      for (k, a_ik) in self.row(i) {
+        for (j, b_kj) in b.row(k) {
+          T::mul_add(&mut c_row[j], a_ik, b_kj);
+          operations += 1;
+        }
+      }
+    }
+    record_operations(operations);
+  }
+}
+
+impl<T : Semiring> From<&Matrix<T>> for CsrMatrix<T> {
+  /// Keeps the elements of `matrix` which are not zero
+  fn from(matrix : &Matrix<T>) -> Self {
+    Self::build(matrix, None).unwrap()
+  }
+}
+
+impl<T : Semiring> From<CsrMatrix<T>> for Matrix<T> {
+  /// Fills in the elements which are not held from `Semiring::zero_matrix`
+  fn from(matrix : CsrMatrix<T>) -> Self {
+    let mut dense = T::zero_matrix(matrix.rows, matrix.cols);
+    for (i, row) in dense.iter_mut().enumerate() {
+      for (j, x) in matrix.row(i) {
+        row[j] = x.clone();
+      }
+    }
+    dense
+  }
+}
+
+impl<T : Sendable> Sendable for CsrMatrix<T> {
+  fn payload_size(&self) -> usize {
+    self.values.iter().map(Sendable::payload_size).sum::<usize>()
+      + (self.col_indices.len() + self.row_starts.len()) * size_of::<usize>()
+  }
+}
+
+/// Bytes `matrix` takes in dense and in CSR form, counted in one pass which
+/// leaves out the elements `Sendable::is_blank` reports
+pub fn block_sizes<T : Sendable>(matrix : &Matrix<T>) -> (usize, usize) {
+  let (dense, held, count) = matrix.iter().flatten().fold((0, 0, 0), |(dense, held, count), x| {
+    let bytes = x.payload_size();
+    if x.is_blank() { (dense + bytes, held, count) } else { (dense + bytes, held + bytes, count + 1) }
+  });
+  (dense, held + (count + matrix.len() + 1) * size_of::<usize>())
+}
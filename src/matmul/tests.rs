@@ -1,8 +1,28 @@
 use std::collections::VecDeque;
+use std::time::Duration;
 
 use crate::processor::get_submatrices;
+use crate::processor::taurus::{TaurusNetworkBuilder, TimedTaurusCore};
+use crate::processor::distribution::Distribution;
+use comm_method::{Hash, FoxOtto, PipeFoxOtto, Cannon, Summa, Dns, Cannon25D, Strassen};
 
 use super::*;
+use dense::DenseMatrix;
+use sparse::CsrMatrix;
+use crate::types::semiring::MinPlus;
+use crate::types::Msg;
+
+/// A `rows` x `cols` matrix of small values, positive, negative and zero.
+/// No two of its first 11 rows or columns are the same, so blocks put in the
+/// wrong place show up, and different seeds give different matrices.
+fn rectangular(rows : usize, cols : usize, seed : usize) -> Matrix<isize> {
+  (0..rows).map(|i| (0..cols).map(|j| ((i * 7 + j * 3 + seed) % 11) as isize - 5).collect()).collect()
+}
+
+/// Message passed between timed cores multiplying matrices of `T`
+type Timed<T> = (Matrix<T>, Duration);
+/// Timed core multiplying matrices of `T`
+type TimedCore<T> = TimedTaurusCore<Timed<T>>;
 
 #[test]
 fn test_serial_matrix_multiplication_square(){
@@ -16,7 +36,7 @@ fn test_serial_matrix_multiplication_square(){
     vec![3,4]
   ];
 
-  let matrix_c : Matrix<isize> = isize::initial_c(&matrix_a, &matrix_b);
+  let matrix_c : Matrix<isize> = isize::zero_matrix(matrix_a.len(), matrix_b[0].len());
   let result = serial_matmul(&matrix_a, &matrix_b, &matrix_c);
 
   assert_eq!(result, vec![
@@ -37,7 +57,7 @@ fn test_serial_matrix_multiplication_non_square(){
     vec![3,4]
   ];
 
-  let matrix_c : Matrix<isize> = isize::initial_c(&matrix_a, &matrix_b);
+  let matrix_c : Matrix<isize> = isize::zero_matrix(matrix_a.len(), matrix_b[0].len());
 
   let result = serial_matmul(&matrix_a, &matrix_b, &matrix_c);
 
@@ -114,3 +134,881 @@ fn test_cannon_shift_b(){
     assert_eq!(res, correct);
   }
 }
+
+fn block_cyclic_mult<F>(matrix_a : &Matrix<isize>, matrix_b : &Matrix<isize>) -> Matrix<isize>
+where F : CommMethod<isize, TaurusCore<Matrix<isize>>> {
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2,2, TaurusNetworkBuilder);
+  let mut p = MatMul::new(&mut processor).with_distribution(Distribution::block_cyclic(2, 2, 2, 2));
+  p.parallel_mult::<F>(matrix_a.clone(), matrix_b.clone())
+}
+
+#[test]
+fn test_block_cyclic_matrix_mult(){
+  let matrix_a = rectangular(7, 7, 0);
+  let matrix_b = rectangular(7, 7, 1);
+  let expected = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(matrix_a.len(), matrix_b[0].len()));
+
+  assert_eq!(block_cyclic_mult::<Hash>(&matrix_a, &matrix_b), expected);
+  assert_eq!(block_cyclic_mult::<FoxOtto>(&matrix_a, &matrix_b), expected);
+  assert_eq!(block_cyclic_mult::<Cannon>(&matrix_a, &matrix_b), expected);
+  assert_eq!(block_cyclic_mult::<Summa<3>>(&matrix_a, &matrix_b), expected);
+}
+
+/// Product of `matrix_a` and `matrix_b` by `F` on a `rows` x `cols` grid
+fn grid_mult<F>(rows : usize, cols : usize, matrix_a : &Matrix<isize>, matrix_b : &Matrix<isize>) -> Matrix<isize>
+where F : CommMethod<isize, TaurusCore<Matrix<isize>>> {
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(rows, cols, TaurusNetworkBuilder);
+  MatMul::new(&mut processor).parallel_mult::<F>(matrix_a.clone(), matrix_b.clone())
+}
+
+#[test]
+fn test_summa_panel_widths(){
+  let matrix_a = rectangular(6, 6, 0);
+  let matrix_b = rectangular(6, 6, 1);
+  let expected = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(matrix_a.len(), matrix_b[0].len()));
+
+  // Panels narrower than, equal to, straddling and wider than the blocks
+  assert_eq!(grid_mult::<Summa<1>>(3, 3, &matrix_a, &matrix_b), expected);
+  assert_eq!(grid_mult::<Summa<2>>(3, 3, &matrix_a, &matrix_b), expected);
+  assert_eq!(grid_mult::<Summa<3>>(3, 3, &matrix_a, &matrix_b), expected);
+  assert_eq!(grid_mult::<Summa>(3, 3, &matrix_a, &matrix_b), expected);
+}
+
+#[test]
+fn test_summa_rectangular_grid(){
+  let matrix_a = rectangular(5, 7, 0);
+  let matrix_b = rectangular(7, 4, 1);
+  let expected = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(matrix_a.len(), matrix_b[0].len()));
+
+  assert_eq!(grid_mult::<Summa<2>>(2, 3, &matrix_a, &matrix_b), expected);
+  assert_eq!(grid_mult::<Summa<2>>(3, 2, &matrix_a, &matrix_b), expected);
+}
+
+#[test]
+fn test_dist_matrix_scatter_gather(){
+  let matrix = rectangular(5, 7, 0);
+
+  let block = dist::DistMatrix::scatter(&matrix, Distribution::block(2, 2));
+  let block_cyclic = dist::DistMatrix::scatter(&matrix, Distribution::block_cyclic(2, 2, 2, 2));
+
+  assert_eq!(block.gather(), matrix);
+  assert_eq!(block_cyclic.gather(), matrix);
+}
+
+#[test]
+fn test_dist_matrix_chained_operations(){
+  let matrix_a = rectangular(6, 6, 0);
+  let matrix_b = rectangular(6, 6, 1);
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(3,3, TaurusNetworkBuilder);
+
+  for distribution in [Distribution::block(3, 3), Distribution::block_cyclic(3, 3, 1, 1)] {
+    let a = dist::DistMatrix::scatter(&matrix_a, distribution.clone());
+    let b = dist::DistMatrix::scatter(&matrix_b, distribution);
+
+    let ab = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(matrix_a.len(), matrix_b[0].len()));
+    assert_eq!(a.multiply::<Hash, _, _>(&b, &mut processor).gather(), ab);
+    assert_eq!(a.multiply::<FoxOtto, _, _>(&b, &mut processor).gather(), ab);
+    assert_eq!(a.multiply::<Cannon, _, _>(&b, &mut processor).gather(), ab);
+
+    // (A B + A) * 2, without leaving the processor
+    let result = a.multiply::<Cannon, _, _>(&b, &mut processor)
+      .add(&a, &mut processor)
+      .map(&mut processor, |x| x * 2)
+      .gather();
+    let expected : Matrix<isize> = ab.iter().zip(matrix_a.iter())
+      .map(|(r, s)| r.iter().zip(s.iter()).map(|(x, y)| (x + y) * 2).collect())
+      .collect();
+    assert_eq!(result, expected);
+  }
+}
+
+#[test]
+fn test_dist_matrix_add_over_semiring(){
+  let lengths = |seed| -> Matrix<MinPlus> {
+    rectangular(4, 4, seed).into_iter().map(|row| row.into_iter().map(|x| MinPlus(x as f64)).collect()).collect()
+  };
+  let (matrix_a, matrix_b) = (lengths(0), lengths(1));
+  let mut processor : Processor <Matrix<MinPlus>, Matrix<MinPlus>, TaurusCore<Matrix<MinPlus>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+
+  let a = dist::DistMatrix::scatter(&matrix_a, Distribution::block(2, 2));
+  let b = dist::DistMatrix::scatter(&matrix_b, Distribution::block(2, 2));
+  let expected : Matrix<MinPlus> = matrix_a.iter().zip(&matrix_b)
+    .map(|(r, s)| r.iter().zip(s).map(|(x, y)| MinPlus(x.0.min(y.0))).collect())
+    .collect();
+  assert_eq!(a.add(&b, &mut processor).gather(), expected);
+}
+
+#[test]
+fn test_dist_matrix_multiply_non_square_grid(){
+  let (matrix_a, matrix_b) = (rectangular(5, 7, 0), rectangular(7, 4, 1));
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2, 3, TaurusNetworkBuilder);
+  let a = dist::DistMatrix::scatter(&matrix_a, Distribution::block(2, 3));
+  let b = dist::DistMatrix::scatter(&matrix_b, Distribution::block(2, 3));
+
+  assert_eq!(a.multiply::<Summa<2>, _, _>(&b, &mut processor).gather(),
+             serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(5, 4)));
+}
+
+#[test]
+#[should_panic(expected = "distributed over a different grid from the processor")]
+fn test_dist_matrix_multiply_checks_processor(){
+  let matrix = rectangular(4, 4, 0);
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+  let a = dist::DistMatrix::scatter(&matrix, Distribution::block(3, 3));
+  a.multiply::<Cannon, _, _>(&a, &mut processor);
+}
+
+#[test]
+fn test_dist_matrix_transpose(){
+  let matrix = rectangular(5, 7, 0);
+  let transposed : Matrix<isize> = (0..7).map(|j| matrix.iter().map(|row| row[j]).collect()).collect();
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2,2, TaurusNetworkBuilder);
+
+  for distribution in [Distribution::block(2, 2), Distribution::block_cyclic(2, 2, 2, 2)] {
+    let matrix = dist::DistMatrix::scatter(&matrix, distribution.clone());
+    let result = matrix.transpose(&mut processor);
+
+    assert_eq!((result.rows(), result.cols()), (7, 5));
+    assert_eq!(result.gather(), transposed);
+    assert_eq!(result, dist::DistMatrix::scatter(&transposed, distribution));
+  }
+}
+
+#[test]
+fn test_dist_matrix_redistribute_probed(){
+  use crate::processor::{ProbeProcessor, distribution::Skew};
+  use crate::processor::probe::ThreadTimeProber;
+  use crate::processor::taurus::TimeTaurusNetworkBuilder;
+
+  type Prober = ThreadTimeProber<Matrix<isize>, TimedCore<isize>>;
+
+  let matrix = rectangular(6, 6, 0);
+  let mut processor : ProbeProcessor<Duration, Matrix<isize>, Timed<isize>, TimedCore<isize>> =
+    ProbeProcessor::new(2, 2, TimeTaurusNetworkBuilder::new(1000000, 1000000000, 0));
+
+  let skewed = dist::DistMatrix::scatter(&matrix, Distribution::skewed(Distribution::block(2, 2), Skew::Left));
+  let unskewed = skewed.redistribute::<Prober, _>(&mut processor, Distribution::block(2, 2));
+  let block_cyclic = unskewed.redistribute::<Prober, _>(&mut processor, Distribution::block_cyclic(2, 2, 1, 1));
+
+  assert_eq!(unskewed, dist::DistMatrix::scatter(&matrix, Distribution::block(2, 2)));
+  assert_eq!(block_cyclic.gather(), matrix);
+  // Every core forwarded messages over links with 1ms latency
+  assert_eq!(processor.debug_stats().len(), 8);
+  assert!(processor.debug_stats().iter().all(|debug| debug.stat >= Duration::from_millis(1)));
+}
+
+#[test]
+fn test_memory_footprint_hash_vs_cannon(){
+  use crate::processor::ProbeProcessor;
+  use crate::processor::probe::stack::{StackProber, MemoryFootprint};
+  use crate::processor::taurus::TimeTaurusNetworkBuilder;
+
+  type Prober = StackProber<Matrix<isize>, TimedCore<isize>, MemoryFootprint>;
+
+  fn peaks<F : CommMethod<isize, Prober>>() -> Vec<usize> {
+    let matrix = rectangular(4, 4, 0);
+    let mut processor : ProbeProcessor<(Duration, MemoryFootprint), Matrix<isize>, Timed<isize>, TimedCore<isize>> =
+      ProbeProcessor::new(2, 2, TimeTaurusNetworkBuilder::new(1000, 1000000000, 0));
+    MatMul::<_, Prober, _>::new(&mut processor).parallel_mult::<F>(matrix.clone(), matrix);
+    processor.debug_stats().iter().map(|debug| debug.stat.1.peak).collect()
+  }
+
+  // Each core holds 2x2 blocks of 32 bytes. Hash keeps both broadcast copies
+  // next to its local A, B and C, while Cannon passes its blocks on.
+  assert_eq!(peaks::<Hash>(), vec![160; 4]);
+  assert_eq!(peaks::<FoxOtto>(), vec![128; 4]);
+  assert_eq!(peaks::<Cannon>(), vec![96; 4]);
+}
+
+#[test]
+#[should_panic(expected = "out of memory: requested 96 bytes with 90 of 90 available")]
+fn test_memory_limit_too_small_for_local_blocks(){
+  use crate::processor::memory::MemoryLimitNetworkBuilder;
+
+  let matrix = rectangular(4, 4, 0);
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2, 2, MemoryLimitNetworkBuilder::new(TaurusNetworkBuilder, 90));
+  MatMul::new(&mut processor).parallel_mult::<Cannon>(matrix.clone(), matrix);
+}
+
+#[test]
+fn test_memory_limit_fits(){
+  use crate::processor::memory::MemoryLimitNetworkBuilder;
+
+  let matrix = rectangular(4, 4, 0);
+  let expected = serial_matmul(&matrix, &matrix, &isize::zero_matrix(matrix.len(), matrix[0].len()));
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2, 2, MemoryLimitNetworkBuilder::new(TaurusNetworkBuilder, 1024));
+  assert_eq!(MatMul::new(&mut processor).parallel_mult::<Cannon>(matrix.clone(), matrix), expected);
+}
+
+#[test]
+fn test_critical_path_of_pipe_fox_otto(){
+  use crate::processor::ProbeProcessor;
+  use crate::processor::probe::stack::{StackProber, Trace};
+  use crate::processor::taurus::{TimeTaurusNetworkBuilder, TaurusOption};
+  use comm_method::PipeFoxOtto;
+
+  type Prober = StackProber<Matrix<isize>, TimedCore<isize>, Trace<TaurusOption>>;
+
+  let matrix = rectangular(6, 6, 0);
+  type Stat = (Duration, Trace<TaurusOption>);
+  let mut processor : ProbeProcessor<Stat, Matrix<isize>, Timed<isize>, TimedCore<isize>> =
+    ProbeProcessor::new(3, 3, TimeTaurusNetworkBuilder::new(1000000, 1000000000, 0));
+  MatMul::<_, Prober, _>::new(&mut processor).parallel_mult::<PipeFoxOtto>(matrix.clone(), matrix);
+
+  let path = processor.critical_path();
+  let slowest = processor.debug_stats().iter().map(|debug| debug.stat.0).max().unwrap();
+
+  assert_eq!(path.length(), slowest);
+  // Every iteration waits on at least one 1ms message
+  assert!(path.links().len() >= 3);
+}
+
+#[test]
+fn test_operation_count_per_core(){
+  use crate::processor::ProbeProcessor;
+  use crate::processor::probe::stack::StackProber;
+  use crate::processor::probe::ops::OperationCount;
+  use crate::processor::taurus::TimeTaurusNetworkBuilder;
+
+  type Prober = StackProber<Matrix<isize>, TimedCore<isize>, OperationCount>;
+
+  let matrix = rectangular(4, 4, 0);
+  let expected = serial_matmul(&matrix, &matrix, &vec![vec![0; 4]; 4]);
+
+  let mut processor : ProbeProcessor<(Duration, usize), Matrix<isize>, Timed<isize>, TimedCore<isize>> =
+    ProbeProcessor::new(2, 2, TimeTaurusNetworkBuilder::new(0, 1000000000, 0));
+  let result = MatMul::<_, Prober, _>::new(&mut processor).parallel_mult::<Cannon>(matrix.clone(), matrix.clone());
+  assert_eq!(result, expected);
+
+  // Each core does 2 iterations of 2x2x2 multiply-adds
+  assert!(processor.debug_stats().iter().all(|debug| debug.stat.1 == 16));
+  assert_eq!(processor.total_operations(), 64);
+
+  // The counts of a second run do not include the first
+  MatMul::<_, Prober, _>::new(&mut processor).parallel_mult::<Hash>(matrix.clone(), matrix);
+  assert_eq!(processor.max_operations(), Some(16));
+  assert_eq!(processor.total_operations(), 128);
+}
+
+fn stacked_mult<F>(side : usize, layers : usize, matrix_a : &Matrix<isize>, matrix_b : &Matrix<isize>) -> Matrix<isize>
+where F : CommMethod<isize, TaurusCore<Matrix<isize>>> {
+  use crate::processor::taurus::StackedTaurusNetworkBuilder;
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(side * layers, side, StackedTaurusNetworkBuilder::new(layers));
+  let mut matmul = MatMul::new(&mut processor);
+  let product = matmul.parallel_mult::<F>(matrix_a.clone(), matrix_b.clone());
+  // The cores are handed back and can run a second multiplication
+  assert_eq!(matmul.parallel_mult::<F>(matrix_a.clone(), matrix_b.clone()), product);
+  product
+}
+
+#[test]
+fn test_dns_matrix_mult(){
+  let matrix_a = rectangular(7, 7, 0);
+  let matrix_b = rectangular(7, 7, 1);
+  let expected = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(matrix_a.len(), matrix_b[0].len()));
+
+  assert_eq!(stacked_mult::<Dns>(2, 2, &matrix_a, &matrix_b), expected);
+  assert_eq!(stacked_mult::<Dns>(3, 3, &matrix_a, &matrix_b), expected);
+}
+
+#[test]
+fn test_cannon_25d_matrix_mult(){
+  let matrix_a = rectangular(7, 7, 0);
+  let matrix_b = rectangular(7, 7, 1);
+  let expected = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(matrix_a.len(), matrix_b[0].len()));
+
+  // One layer is plain Cannon, and 2 layers do not split 3 steps evenly
+  assert_eq!(stacked_mult::<Cannon25D>(3, 1, &matrix_a, &matrix_b), expected);
+  assert_eq!(stacked_mult::<Cannon25D>(2, 2, &matrix_a, &matrix_b), expected);
+  assert_eq!(stacked_mult::<Cannon25D>(3, 2, &matrix_a, &matrix_b), expected);
+}
+
+#[test]
+fn test_stacked_methods_probed(){
+  use crate::processor::ProbeProcessor;
+  use crate::processor::probe::stack::{StackProber, MessageCount};
+  use crate::processor::taurus::TimeTaurusNetworkBuilder;
+
+  type Prober = StackProber<Matrix<isize>, TimedCore<isize>, MessageCount>;
+
+  let matrix = rectangular(4, 4, 0);
+  let expected = serial_matmul(&matrix, &matrix, &isize::zero_matrix(matrix.len(), matrix[0].len()));
+
+  let mut processor : ProbeProcessor<(Duration, MessageCount), Matrix<isize>, Timed<isize>, TimedCore<isize>> =
+    ProbeProcessor::new(4, 2, TimeTaurusNetworkBuilder::new(1000, 1000000000, 0).with_layers(2));
+  let result = MatMul::<_, Prober, _>::new(&mut processor).parallel_mult::<Cannon25D>(matrix.clone(), matrix.clone());
+  assert_eq!(result, expected);
+
+  // Each layer runs a single step of Cannon, so after replication and the
+  // skew the second layer only sends its partial product up the fibre
+  let sent = |row : usize, col : usize| processor.debug_stats().iter()
+    .find(|debug| (debug.row, debug.col) == (row, col)).unwrap().stat.1.messages_sent;
+  assert_eq!(sent(0, 0), 2);
+  assert_eq!(sent(2, 0), 3);
+  assert_eq!(sent(3, 1), 1);
+}
+
+#[test]
+fn test_strassen_matrix_mult(){
+  let matrix_a = rectangular(12, 12, 0);
+  let matrix_b = rectangular(12, 12, 1);
+  let expected = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(matrix_a.len(), matrix_b[0].len()));
+
+  assert_eq!(grid_mult::<Strassen<Hash>>(2, 2, &matrix_a, &matrix_b), expected);
+  assert_eq!(grid_mult::<Strassen<FoxOtto, 2>>(3, 3, &matrix_a, &matrix_b), expected);
+  assert_eq!(grid_mult::<Strassen<Cannon, 2>>(2, 2, &matrix_a, &matrix_b), expected);
+  assert_eq!(block_cyclic_mult::<Strassen<Cannon>>(&matrix_a, &matrix_b), expected);
+  assert_eq!(stacked_mult::<Strassen<Dns>>(2, 2, &matrix_a, &matrix_b), expected);
+
+  // Rectangular matrices on a rectangular grid
+  let matrix_a = rectangular(8, 4, 0);
+  let matrix_b = rectangular(4, 6, 1);
+  let expected = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(matrix_a.len(), matrix_b[0].len()));
+  assert_eq!(grid_mult::<Strassen<Summa<1>>>(2, 3, &matrix_a, &matrix_b), expected);
+}
+
+#[test]
+fn test_strassen_square_and_floats(){
+  let matrix : Matrix<f64> = rectangular(8, 8, 0).into_iter()
+    .map(|row| row.into_iter().map(|x| x as f64).collect()).collect();
+  let mut processor : Processor <Matrix<f64>, Matrix<f64>, TaurusCore<Matrix<f64>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+  let expected = MatMul::new(&mut processor).parallel_square::<Cannon>(matrix.clone(), 3);
+
+  // Every square realigns the blocks of each quadrant for Cannon
+  let result = MatMul::new(&mut processor).parallel_square::<Strassen<Cannon>>(matrix, 3);
+  assert_eq!(result, expected);
+}
+
+#[test]
+fn test_strassen_saves_operations(){
+  use crate::processor::ProbeProcessor;
+  use crate::processor::probe::stack::StackProber;
+  use crate::processor::probe::ops::OperationCount;
+  use crate::processor::taurus::TimeTaurusNetworkBuilder;
+
+  type Prober = StackProber<Matrix<isize>, TimedCore<isize>, OperationCount>;
+
+  let matrix = rectangular(8, 8, 0);
+  let expected = serial_matmul(&matrix, &matrix, &isize::zero_matrix(matrix.len(), matrix[0].len()));
+
+  let mut processor : ProbeProcessor<(Duration, usize), Matrix<isize>, Timed<isize>, TimedCore<isize>> =
+    ProbeProcessor::new(2, 2, TimeTaurusNetworkBuilder::new(0, 1000000000, 0));
+  let result = MatMul::<_, Prober, _>::new(&mut processor).parallel_mult::<Cannon>(matrix.clone(), matrix.clone());
+  assert_eq!(result, expected);
+  // 2 iterations of 4x4x4 on each of 4 cores
+  assert_eq!(processor.total_operations(), 512);
+
+  let result = MatMul::<_, Prober, _>::new(&mut processor).parallel_mult::<Strassen<Cannon>>(matrix.clone(), matrix);
+  assert_eq!(result, expected);
+  // 7 products of 2 iterations of 2x2x2 on each core
+  assert_eq!(processor.total_operations(), 512 + 448);
+}
+
+fn adjacency<T : Semiring>(size : usize, edges : &[(usize, usize, T)]) -> Matrix<T> {
+  let mut matrix = T::identity_matrix(size);
+  for (from, to, weight) in edges {
+    matrix[*from][*to] = weight.clone();
+  }
+  matrix
+}
+
+fn closure<T : Semiring + Sendable + PartialEq + 'static>(matrix : Matrix<T>) -> Matrix<T> {
+  assert!(T::IDEMPOTENT);
+  let mut processor : Processor <Matrix<T>, Matrix<T>, TaurusCore<Matrix<T>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+  MatMul::new(&mut processor).parallel_square::<Cannon>(matrix, 2)
+}
+
+#[test]
+fn test_semiring_closures(){
+  use crate::types::semiring::{MinPlus, MaxPlus, MaxMin};
+  let edges = [(0, 1, 1.0), (1, 2, 2.0), (0, 2, 5.0), (2, 3, 1.0)];
+  let inf = f64::INFINITY;
+
+  let shortest = closure(adjacency(4, &edges.map(|(i, j, w)| (i, j, MinPlus(w)))));
+  assert_eq!(shortest[0], vec![MinPlus(0.0), MinPlus(1.0), MinPlus(3.0), MinPlus(4.0)]);
+  assert_eq!(shortest[3], vec![MinPlus(inf), MinPlus(inf), MinPlus(inf), MinPlus(0.0)]);
+
+  let longest = closure(adjacency(4, &edges.map(|(i, j, w)| (i, j, MaxPlus(w)))));
+  assert_eq!(longest[0], vec![MaxPlus(0.0), MaxPlus(1.0), MaxPlus(5.0), MaxPlus(6.0)]);
+
+  let widest = closure(adjacency(4, &edges.map(|(i, j, w)| (i, j, MaxMin(w)))));
+  assert_eq!(widest[0], vec![MaxMin(inf), MaxMin(1.0), MaxMin(5.0), MaxMin(1.0)]);
+  assert_eq!(widest[1][3], MaxMin(1.0));
+
+  let reachable = closure(adjacency(4, &edges.map(|(i, j, _)| (i, j, true))));
+  assert_eq!(reachable[1], vec![false, true, true, true]);
+  assert_eq!(reachable[3], vec![false, false, false, true]);
+}
+
+#[test]
+fn test_min_plus_path_counting(){
+  use crate::types::semiring::MinPlusCount;
+
+  // Two shortest paths of 2 hops from 0 to 3, and a longer one through 4
+  let mut matrix = MinPlusCount::zero_matrix(6, 6);
+  for (from, to, length) in [(0, 1, 1.0), (0, 2, 1.0), (1, 3, 1.0), (2, 3, 1.0), (0, 4, 2.0), (4, 3, 2.0)] {
+    matrix[from][to] = MinPlusCount::new(length);
+  }
+  let expected = serial_matmul(&matrix, &matrix, &MinPlusCount::zero_matrix(6, 6));
+  assert_eq!(expected[0][3], MinPlusCount { length : 2.0, count : 2 });
+  assert_eq!(expected[0][1], MinPlusCount::zero());
+
+  let mut processor : Processor <Matrix<MinPlusCount>, Matrix<MinPlusCount>, TaurusCore<Matrix<MinPlusCount>>> = 
+    Processor::new(3, 2, TaurusNetworkBuilder);
+  assert_eq!(MatMul::new(&mut processor).parallel_mult::<Summa<2>>(matrix.clone(), matrix), expected);
+}
+
+#[test]
+fn test_msg_keeps_predecessors(){
+  use crate::types::Msg;
+  // 0 -> 1 -> 2 is shorter than the direct edge 0 -> 2
+  let mut matrix = Msg::identity_matrix(3);
+  matrix[0][1] = Msg::new(1.0, 0);
+  matrix[1][2] = Msg::new(1.0, 1);
+  matrix[0][2] = Msg::new(3.0, 0);
+
+  let (w, p) = Msg::unzip(&closure(matrix));
+  assert_eq!(w, vec![vec![0.0, 1.0, 2.0], vec![-1.0, 0.0, 1.0], vec![-1.0, -1.0, 0.0]]);
+  assert_eq!(p[0], vec![0, 0, 1]);
+}
+
+#[test]
+fn test_msg_zero_length_edge(){
+  use crate::types::Msg;
+  // The edge 1 -> 2 has length 0, but is still a hop from 1
+  let mut matrix = Msg::identity_matrix(3);
+  matrix[0][1] = Msg::new(1.0, 0);
+  matrix[1][2] = Msg::new(0.0, 1);
+
+  let (w, p) = Msg::unzip(&closure(matrix));
+  assert_eq!(w[0], vec![0.0, 1.0, 1.0]);
+  assert_eq!(p, vec![vec![0, 0, 1], vec![0, 1, 1], vec![0, 1, 2]]);
+  assert_eq!(Msg::identity_matrix(2)[1][1], Msg::one());
+}
+
+#[test]
+fn test_parallel_pow(){
+  let matrix = rectangular(5, 5, 0);
+  let mut expected = isize::identity_matrix(5);
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+
+  for power in 0..8 {
+    assert_eq!(MatMul::new(&mut processor).parallel_pow::<Cannon>(matrix.clone(), power), expected);
+    assert_eq!(MatMul::new(&mut processor).parallel_pow::<Summa<2>>(matrix.clone(), power), expected);
+    expected = serial_matmul(&expected, &matrix, &isize::zero_matrix(5, 5));
+  }
+}
+
+#[test]
+fn test_parallel_closure(){
+  use crate::types::semiring::MinPlus;
+  let edges = [(0, 1, MinPlus(1.0)), (1, 2, MinPlus(2.0)), (0, 2, MinPlus(5.0)), (2, 3, MinPlus(1.0)), (3, 4, MinPlus(1.0))];
+  // The closure adds the diagonal itself
+  let mut matrix = MinPlus::zero_matrix(5, 5);
+  for (from, to, length) in edges {
+    matrix[from][to] = length;
+  }
+  let expected = closure(adjacency(5, &edges));
+  assert_eq!(expected[0], vec![MinPlus(0.0), MinPlus(1.0), MinPlus(3.0), MinPlus(4.0), MinPlus(5.0)]);
+
+  let mut processor : Processor <Matrix<MinPlus>, Matrix<MinPlus>, TaurusCore<Matrix<MinPlus>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+  assert_eq!(MatMul::new(&mut processor).parallel_closure::<Hash>(matrix.clone()), expected);
+  assert_eq!(MatMul::new(&mut processor).parallel_closure::<Cannon>(matrix.clone()), expected);
+  assert_eq!(stacked_closure(matrix), expected);
+}
+
+#[test]
+fn test_parallel_closure_needs_idempotent_semiring(){
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+  let result = MatMul::new(&mut processor).try_parallel_closure::<Cannon>(rectangular(4, 4, 0));
+  assert_eq!(result, Err(MatMulError::NotIdempotent));
+}
+
+fn stacked_closure<T : Semiring + Sendable + PartialEq + 'static>(matrix : Matrix<T>) -> Matrix<T> {
+  use crate::processor::taurus::StackedTaurusNetworkBuilder;
+  let mut processor : Processor <Matrix<T>, Matrix<T>, TaurusCore<Matrix<T>>> = 
+    Processor::new(4, 2, StackedTaurusNetworkBuilder::new(2));
+  MatMul::new(&mut processor).parallel_closure::<Dns>(matrix)
+}
+
+#[test]
+fn test_parallel_closure_stops_early(){
+  use crate::processor::ProbeProcessor;
+  use crate::processor::probe::stack::StackProber;
+  use crate::processor::probe::ops::OperationCount;
+  use crate::processor::taurus::TimeTaurusNetworkBuilder;
+
+  type Prober = StackProber<Matrix<bool>, TimedCore<bool>, OperationCount>;
+
+  // A path through all 8 nodes needs 3 squarings and a fourth to notice
+  // nothing changes, while a complete graph stops after the first
+  let path : Matrix<bool> = (0..8).map(|i| (0..8).map(|j| j == i + 1).collect()).collect();
+  let complete : Matrix<bool> = vec![vec![true; 8]; 8];
+  let mut processor : ProbeProcessor<(Duration, usize), Matrix<bool>, Timed<bool>, TimedCore<bool>> =
+    ProbeProcessor::new(2, 2, TimeTaurusNetworkBuilder::new(0, 1000000000, 0));
+
+  let closure = MatMul::<_, Prober, _>::new(&mut processor).parallel_closure::<Cannon>(path);
+  assert!((0..8).all(|i| (0..8).all(|j| closure[i][j] == (j >= i))));
+  // Each squaring does 2 iterations of 4x4x4 on every core, less the terms
+  // the sparse kernels skip while the blocks of the path hold at most one
+  // edge
+  assert_eq!(processor.max_operations(), Some(4 * 128 - 60));
+  let path_operations = processor.total_operations();
+
+  // The dense blocks of the complete graph pay for exactly one squaring
+  let closure = MatMul::<_, Prober, _>::new(&mut processor).parallel_closure::<Cannon>(complete.clone());
+  assert_eq!(closure, complete);
+  assert_eq!(processor.total_operations() - path_operations, 4 * 128);
+}
+
+fn cube_on<C, R>(processor : &mut R, matrix : &Matrix<isize>) -> Matrix<isize>
+where C : Core<Matrix<isize>, ChannelOption = TaurusOption>,
+      R : Runner<Matrix<isize>, C> {
+  MatMul::new(processor).parallel_pow::<Cannon>(matrix.clone(), 3)
+}
+
+#[test]
+fn test_matmul_over_any_runner(){
+  use crate::processor::ProbeProcessor;
+  use crate::processor::probe::ThreadTimeProber;
+  use crate::processor::taurus::TimeTaurusNetworkBuilder;
+
+  type Prober = ThreadTimeProber<Matrix<isize>, TimedCore<isize>>;
+
+  let matrix = rectangular(4, 4, 0);
+  let squared = serial_matmul(&matrix, &matrix, &isize::zero_matrix(4, 4));
+  let expected = serial_matmul(&squared, &matrix, &isize::zero_matrix(4, 4));
+
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+  assert_eq!(cube_on(&mut processor, &matrix), expected);
+
+  let mut probed : ProbeProcessor<Duration, Matrix<isize>, Timed<isize>, TimedCore<isize>> =
+    ProbeProcessor::new(2, 2, TimeTaurusNetworkBuilder::new(0, 1000000000, 0));
+  assert_eq!(cube_on::<Prober, _>(&mut probed, &matrix), expected);
+  assert_eq!(probed.debug_stats().len(), 4);
+}
+
+#[test]
+fn test_rectangular_matrix_mult(){
+  // Inner dimensions smaller than the grid leave some cores without blocks
+  for (m, k, n) in [(5, 7, 3), (3, 7, 5), (7, 2, 6), (2, 9, 2), (6, 1, 4), (1, 5, 1)] {
+    let matrix_a = rectangular(m, k, 0);
+    let matrix_b = rectangular(k, n, 3);
+    let expected = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(m, n));
+    for side in [2, 3] {
+      assert_eq!(grid_mult::<Hash>(side, side, &matrix_a, &matrix_b), expected);
+      assert_eq!(grid_mult::<FoxOtto>(side, side, &matrix_a, &matrix_b), expected);
+      assert_eq!(grid_mult::<PipeFoxOtto>(side, side, &matrix_a, &matrix_b), expected);
+      assert_eq!(grid_mult::<Cannon>(side, side, &matrix_a, &matrix_b), expected);
+      assert_eq!(grid_mult::<Summa<2>>(side, side + 1, &matrix_a, &matrix_b), expected);
+      assert_eq!(stacked_mult::<Dns>(side, side, &matrix_a, &matrix_b), expected);
+      assert_eq!(stacked_mult::<Cannon25D>(side, 2, &matrix_a, &matrix_b), expected);
+    }
+  }
+}
+
+#[test]
+fn test_strassen_rectangular(){
+  let matrix_a = rectangular(4, 6, 0);
+  let matrix_b = rectangular(6, 2, 3);
+  let expected = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(4, 2));
+  assert_eq!(grid_mult::<Strassen<Cannon>>(2, 2, &matrix_a, &matrix_b), expected);
+
+  let matrix_a = rectangular(4, 8, 1);
+  let matrix_b = rectangular(8, 12, 2);
+  let expected = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(4, 12));
+  assert_eq!(grid_mult::<Strassen<Summa, 2>>(2, 3, &matrix_a, &matrix_b), expected);
+}
+
+#[test]
+fn test_shape_errors(){
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+  let mut matmul = MatMul::new(&mut processor);
+
+  assert_eq!(matmul.try_parallel_mult::<Cannon>(rectangular(3, 4, 0), rectangular(5, 2, 0)),
+             Err(MatMulError::Shape(ShapeError::Mismatch { cols_a : 4, rows_b : 5 })));
+  assert_eq!(matmul.try_parallel_mult::<Cannon>(Vec::new(), rectangular(5, 2, 0)),
+             Err(MatMulError::Shape(ShapeError::Empty { matrix : 'A' })));
+  assert_eq!(matmul.try_parallel_mult::<Cannon>(rectangular(3, 4, 0), vec![Vec::new(); 4]),
+             Err(MatMulError::Shape(ShapeError::Empty { matrix : 'B' })));
+  assert_eq!(matmul.try_parallel_mult::<Cannon>(rectangular(3, 4, 0), vec![vec![1, 2], vec![3], vec![4, 5], vec![6, 7]]),
+             Err(MatMulError::Shape(ShapeError::Ragged { matrix : 'B', row : 1, len : 1, cols : 2 })));
+  assert_eq!(matmul.try_parallel_pow::<Cannon>(rectangular(3, 4, 0), 2),
+             Err(MatMulError::Shape(ShapeError::NotSquare { rows : 3, cols : 4 })));
+  assert_eq!(matmul.try_parallel_mult::<Strassen<Cannon>>(rectangular(4, 3, 0), rectangular(3, 4, 0)),
+             Err(MatMulError::Shape(ShapeError::Indivisible { dimension : 3, blocks : 2 })));
+
+  // The cores are still usable after the errors
+  let (matrix_a, matrix_b) = (rectangular(3, 4, 0), rectangular(4, 5, 1));
+  let expected = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(3, 5));
+  assert_eq!(matmul.try_parallel_mult::<Cannon>(matrix_a, matrix_b), Ok(expected));
+}
+
+#[test]
+fn test_grid_errors(){
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2, 3, TaurusNetworkBuilder);
+  let mut matmul = MatMul::new(&mut processor);
+  let error = matmul.try_parallel_mult::<Cannon>(rectangular(4, 4, 0), rectangular(4, 4, 1)).unwrap_err();
+  assert_eq!(error, MatMulError::Shape(ShapeError::Grid { rows : 2, cols : 3, layers : 1, needs : "needs a square grid" }));
+  assert_eq!(error.to_string(), "The method needs a square grid, but the grid is 2 x 3");
+  assert!(matmul.try_parallel_mult::<Summa>(rectangular(4, 4, 0), rectangular(4, 4, 1)).is_ok());
+
+  // 2 rows cannot be read as layers of 3 x 3
+  let error = matmul.try_parallel_mult::<Dns>(rectangular(4, 4, 0), rectangular(4, 4, 1)).unwrap_err();
+  assert_eq!(error, MatMulError::Shape(ShapeError::Grid { rows : 2, cols : 3, layers : 1,
+                                                          needs : "needs square layers stacked along the rows" }));
+  assert!(matmul.try_parallel_mult::<Cannon25D>(rectangular(4, 4, 0), rectangular(4, 4, 1)).is_err());
+
+  let mut matmul = MatMul::new(&mut processor).with_distribution(Distribution::block(3, 2));
+  let error = matmul.try_parallel_mult::<Summa>(rectangular(4, 4, 0), rectangular(4, 4, 1)).unwrap_err();
+  assert_eq!(error, MatMulError::Shape(ShapeError::Distribution { rows : 3, cols : 2, grid_rows : 2, grid_cols : 3 }));
+
+  // Blocks which are not square split the columns of A and the rows of B
+  // differently, which only SUMMA copes with
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+  let (matrix_a, matrix_b) = (rectangular(7, 7, 0), rectangular(7, 7, 1));
+  let mut matmul = MatMul::new(&mut processor).with_distribution(Distribution::block_cyclic(2, 2, 1, 2));
+  let error = matmul.try_parallel_mult::<Cannon>(matrix_a.clone(), matrix_b.clone()).unwrap_err();
+  assert_eq!(error, MatMulError::Shape(ShapeError::Blocks { block_rows : 1, block_cols : 2 }));
+  assert_eq!(matmul.try_parallel_mult::<Summa<2>>(matrix_a.clone(), matrix_b.clone()),
+             Ok(serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(7, 7))));
+}
+
+#[test]
+#[should_panic(expected = "Cannot multiply A with 4 columns by B with 5 rows")]
+fn test_parallel_mult_panics_on_mismatch(){
+  grid_mult::<Cannon>(2, 2, &rectangular(3, 4, 0), &rectangular(5, 2, 0));
+}
+
+/// Textbook i-j-k product, to check the tiled kernel against
+fn naive_product<T : Semiring>(matrix_a : &Matrix<T>, matrix_b : &Matrix<T>, matrix_c : &Matrix<T>) -> Matrix<T> {
+  (0..matrix_a.len()).map(|i| (0..matrix_c[i].len()).map(|j|
+    (0..matrix_b.len()).fold(matrix_c[i][j].clone(), |acc, k| T::add(acc, T::mul(matrix_a[i][k].clone(), matrix_b[k][j].clone())))
+  ).collect()).collect()
+}
+
+#[test]
+fn test_dense_matrix_conversions(){
+  let matrix = rectangular(3, 5, 0);
+  let dense = DenseMatrix::from(&matrix);
+  assert_eq!((dense.rows(), dense.cols()), (3, 5));
+  assert_eq!(dense.row(1), &matrix[1][..]);
+  assert_eq!(*dense.get(2, 4), matrix[2][4]);
+  assert_eq!(Matrix::from(dense), matrix);
+
+  let no_cols : Matrix<isize> = vec![Vec::new(); 3];
+  assert_eq!(Matrix::from(DenseMatrix::from(no_cols.clone())), no_cols);
+}
+
+#[test]
+fn test_dense_kernel_across_tiles(){
+  // Dimensions either side of the tile size, so the edge tiles are partial
+  for (m, k, n) in [(1, 1, 1), (3, 70, 5), (65, 2, 130), (70, 129, 67)] {
+    let (matrix_a, matrix_b, matrix_c) = (rectangular(m, k, 0), rectangular(k, n, 1), rectangular(m, n, 2));
+    let expected = naive_product(&matrix_a, &matrix_b, &matrix_c);
+
+    let mut product = DenseMatrix::from(&matrix_c);
+    product.mul_add(&DenseMatrix::from(&matrix_a), &DenseMatrix::from(&matrix_b));
+    assert_eq!(Matrix::from(product), expected);
+    let mut product = DenseMatrix::from(&matrix_c);
+    product.mul_add_rows(&matrix_a, &matrix_b);
+    assert_eq!(Matrix::from(product), expected);
+    assert_eq!(serial_matmul(&matrix_a, &matrix_b, &matrix_c), expected);
+  }
+}
+
+#[test]
+fn test_dense_kernel_keeps_tie_order(){
+  // Every pair of distinct nodes is joined by paths of length 2 through all
+  // the other nodes, each reached from a different predecessor. The path
+  // through the lowest node comes first and is kept, across tiles.
+  let size = 70;
+  let matrix : Matrix<Msg> = (0..size).map(|i| (0..size).map(|j|
+    Msg::new(if i == j { 5.0 } else { 1.0 }, i)).collect()).collect();
+  let zero = Msg::zero_matrix(size, size);
+  let product = serial_matmul(&matrix, &matrix, &zero);
+  assert_eq!(product, naive_product(&matrix, &matrix, &zero));
+  assert_eq!((product[3][5].get_w(), product[3][5].get_p()), (2.0, 0));
+  assert_eq!((product[0][1].get_w(), product[0][1].get_p()), (2.0, 2));
+}
+
+#[test]
+fn test_dense_kernel_counts_operations(){
+  use crate::processor::probe::ops::operations;
+  let start = operations();
+  serial_matmul(&rectangular(3, 4, 0), &rectangular(4, 5, 0), &isize::zero_matrix(3, 5));
+  serial_matmul(&rectangular(3, 0, 0), &Vec::new(), &isize::zero_matrix(3, 5));
+  assert_eq!(operations() - start, 3 * 4 * 5);
+}
+
+/// Ring road of `size` nodes where every node also has a shortcut to the
+/// node halfway round, so rows hold 3 of `size` elements
+fn ring_road(size : usize) -> Matrix<MinPlus> {
+  (0..size).map(|i| (0..size).map(|j|
+    if j == (i + 1) % size || j == (i + size - 1) % size { MinPlus(1.0) }
+    else if j == (i + size / 2) % size { MinPlus(5.0) }
+    else { MinPlus::zero() }
+  ).collect()).collect()
+}
+
+#[test]
+fn test_csr_matrix_conversions(){
+  let matrix = ring_road(40);
+  let sparse = CsrMatrix::from(&matrix);
+  assert_eq!((sparse.rows(), sparse.cols(), sparse.nonzeros()), (40, 40, 120));
+  assert_eq!(sparse.row(0).map(|(j, x)| (j, *x)).collect::<Vec<_>>(),
+             vec![(1, MinPlus(1.0)), (20, MinPlus(5.0)), (39, MinPlus(1.0))]);
+  assert_eq!(Matrix::from(sparse), matrix);
+  assert_eq!(CsrMatrix::sparse(&matrix), Some(CsrMatrix::from(&matrix)));
+  assert_eq!(CsrMatrix::sparse(&rectangular(4, 4, 0)), None);
+  assert_eq!(CsrMatrix::<isize>::sparse(&Vec::new()), None);
+
+  // Missing paths come back reached from themselves, as in `zero_matrix`
+  let paths = vec![vec![Msg::new(-1.0, 7), Msg::new(2.0, 0)]];
+  let back = Matrix::from(CsrMatrix::from(&paths));
+  assert_eq!((back[0][0].get_w(), back[0][0].get_p()), (-1.0, 0));
+  assert_eq!(back[0][1], paths[0][1]);
+}
+
+#[test]
+fn test_sparse_kernels(){
+  use crate::processor::probe::ops::operations;
+  let matrix = ring_road(40);
+  let dense = rectangular(40, 6, 0).into_iter()
+    .map(|row| row.into_iter().map(|x| MinPlus(x as f64)).collect()).collect::<Matrix<MinPlus>>();
+  let sparse = CsrMatrix::from(&matrix);
+
+  // Sparse times dense does one multiply-add per element held and column
+  let zero = MinPlus::zero_matrix(40, 6);
+  let mut product = DenseMatrix::from(&zero);
+  let start = operations();
+  sparse.mul_add_dense(&DenseMatrix::from(&dense), &mut product);
+  assert_eq!(operations() - start, 120 * 6);
+  assert_eq!(Matrix::from(product), naive_product(&matrix, &dense, &zero));
+
+  // Sparse times sparse only pairs up elements which are both held
+  let zero = MinPlus::zero_matrix(40, 40);
+  let mut product = DenseMatrix::from(&zero);
+  let start = operations();
+  sparse.mul_add_sparse(&sparse, &mut product);
+  assert_eq!(operations() - start, 120 * 3);
+  let expected = naive_product(&matrix, &matrix, &zero);
+  assert_eq!(Matrix::from(product), expected);
+  assert_eq!(serial_matmul(&matrix, &matrix, &zero), expected);
+}
+
+#[test]
+fn test_sparse_message_sizes(){
+  use crate::broadcast::Sendable;
+  let matrix = ring_road(40);
+  let words = std::mem::size_of::<usize>();
+  // Every element held costs its value and column, every row its start
+  let csr = 120 * (std::mem::size_of::<MinPlus>() + words) + 41 * words;
+  assert_eq!(CsrMatrix::from(&matrix).payload_size(), csr);
+  assert_eq!(sparse::block_sizes(&matrix), (40 * 40 * std::mem::size_of::<MinPlus>(), csr));
+  assert_eq!(matrix.message_size(), csr);
+  assert_eq!(matrix.payload_size(), 40 * 40 * std::mem::size_of::<MinPlus>());
+
+  let dense = rectangular(4, 4, 0);
+  assert_eq!(dense.message_size(), dense.payload_size());
+}
+
+#[test]
+fn test_sparse_blocks_send_fewer_bytes(){
+  use crate::processor::ProbeProcessor;
+  use crate::processor::probe::stack::{StackProber, MessageCount};
+  use crate::processor::taurus::TimeTaurusNetworkBuilder;
+
+  type Prober = StackProber<Matrix<MinPlus>, TimedCore<MinPlus>, MessageCount>;
+
+  let bytes_sent = |matrix : Matrix<MinPlus>| {
+    let mut processor : ProbeProcessor<(Duration, MessageCount), Matrix<MinPlus>, Timed<MinPlus>, TimedCore<MinPlus>> =
+      ProbeProcessor::new(2, 2, TimeTaurusNetworkBuilder::new(0, 1, 0));
+    let expected = serial_matmul(&matrix, &matrix, &MinPlus::zero_matrix(40, 40));
+    let result = MatMul::<_, Prober, _>::new(&mut processor).parallel_mult::<Cannon>(matrix.clone(), matrix);
+    assert_eq!(result, expected);
+    processor.debug_stats().iter().map(|debug| debug.stat.1.bytes_sent).sum::<usize>()
+  };
+  let sparse = bytes_sent(ring_road(40));
+  let dense = bytes_sent(vec![vec![MinPlus(1.0); 40]; 40]);
+  assert!(sparse * 4 < dense, "sparse blocks sent {sparse} bytes against {dense}");
+}
+
+/// SUMMA, except that core (1, 0) drops its product
+struct DropsBlock;
+
+impl CommMethod<isize, TaurusCore<Matrix<isize>>> for DropsBlock {
+  fn matrix_mult(matrix_a : Matrix<isize>, matrix_b : Matrix<isize>, matrix_c : Matrix<isize>,
+                 layout : &Layout, core_info : &mut TaurusCore<Matrix<isize>>) -> Matrix<isize> {
+    let product = <Summa as CommMethod<isize, TaurusCore<Matrix<isize>>>>::matrix_mult(
+      matrix_a, matrix_b, matrix_c.clone(), layout, core_info);
+    if (core_info.row(), core_info.col()) == (1, 0) { matrix_c } else { product }
+  }
+}
+
+#[test]
+fn test_verification_reports_mismatch(){
+  let (matrix_a, matrix_b) = (rectangular(4, 6, 1), rectangular(6, 4, 2));
+  let expected = serial_matmul(&matrix_a, &matrix_b, &isize::zero_matrix(4, 4));
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+
+  // Without verification the wrong product comes back as it is
+  let unchecked = MatMul::new(&mut processor).try_parallel_mult::<DropsBlock>(matrix_a.clone(), matrix_b.clone());
+  assert!(unchecked.is_ok_and(|product| product != expected));
+
+  let mut matmul = MatMul::new(&mut processor).with_verification();
+  let Err(MatMulError::Mismatch(mismatch)) = matmul.try_parallel_mult::<DropsBlock>(matrix_a.clone(), matrix_b.clone()) else {
+    panic!("the dropped block was not reported");
+  };
+  // Core (1, 0) holds rows 2 and 3 and columns 0 and 1
+  let differing : Vec<(usize, usize)> = (2..4).flat_map(|i| (0..2).map(move |j| (i, j)))
+    .filter(|&(i, j)| expected[i][j] != 0).collect();
+  assert_eq!((mismatch.row, mismatch.col), differing[0]);
+  assert_eq!((mismatch.expected, mismatch.got), (expected[mismatch.row][mismatch.col], 0));
+  assert_eq!((mismatch.core, mismatch.count), ((1, 0), differing.len()));
+  assert_eq!(MatMulError::Mismatch(mismatch.clone()).to_string(), format!(
+    "C[{}][{}] from core 1 0 is 0 but the serial product gives {} ({} elements differ)",
+    mismatch.row, mismatch.col, mismatch.expected, mismatch.count));
+
+  // Correct methods pass, and shape errors still come first
+  assert_eq!(matmul.try_parallel_mult::<Cannon>(matrix_a.clone(), matrix_b.clone()), Ok(expected));
+  assert!(matches!(matmul.try_parallel_mult::<Cannon>(matrix_a.clone(), matrix_a),
+                   Err(MatMulError::Shape(ShapeError::Mismatch { .. }))));
+}
+
+#[test]
+fn test_verification_of_powers_and_closures(){
+  use crate::types::semiring::MinPlus;
+  let mut processor : Processor <Matrix<isize>, Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+  let mut matmul = MatMul::new(&mut processor).with_verification();
+  let matrix = vec![vec![1, -1, 0, 0], vec![0, 1, 1, 0], vec![1, 0, 0, -1], vec![0, 0, 1, 1]];
+  assert_eq!(matmul.try_parallel_square::<Cannon>(matrix.clone(), 2), Ok(verify::serial_square(&matrix, 2)));
+  assert_eq!(matmul.try_parallel_pow::<FoxOtto>(matrix.clone(), 5), Ok(verify::serial_pow(&matrix, 5)));
+
+  let mut processor : Processor <Matrix<MinPlus>, Matrix<MinPlus>, TaurusCore<Matrix<MinPlus>>> = 
+    Processor::new(2, 2, TaurusNetworkBuilder);
+  let roads = ring_road(8);
+  let closure = MatMul::new(&mut processor).with_verification().try_parallel_closure::<Summa>(roads);
+  assert_eq!(closure.map(|closure| closure[0][4]), Ok(MinPlus(4.0)));
+}
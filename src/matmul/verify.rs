@@ -0,0 +1,88 @@
+use std::fmt::{self, Debug};
+
+use crate::processor::distribution::Distribution;
+use crate::types::Matrix;
+use super::{Semiring, serial_matmul};
+
+/// First element of a product which differs from the one computed on the
+/// host, along with the core which held it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch<T> {
+  pub row : usize,
+  pub col : usize,
+  pub expected : T,
+  pub got : T,
+  /// Row and column of the core holding the element
+  pub core : (usize, usize),
+  /// Number of elements which differ, including this one
+  pub count : usize,
+}
+
+impl<T : Debug> fmt::Display for Mismatch<T> {
+  fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "C[{}][{}] from core {} {} is {:?} but the serial product gives {:?} ({} elements differ)",
+           self.row, self.col, self.core.0, self.core.1, self.got, self.expected, self.count)
+  }
+}
+
+/// Compares `got` with `expected` element by element, exactly, so methods
+/// which reorder sums of floats may be reported
+pub fn find_mismatch<T : PartialEq + Clone>(distribution : &Distribution, expected : &Matrix<T>,
+                                            got : &Matrix<T>) -> Option<Mismatch<T>> {
+  let mut differing = expected.iter().zip(got).enumerate()
+    .flat_map(|(i, (expected_row, got_row))| expected_row.iter().zip(got_row).enumerate()
+      .filter(|(_, (x, y))| x != y)
+      .map(move |(j, (x, y))| (i, j, x, y)));
+  let (row, col, expected_element, got_element) = differing.next()?;
+  Some(Mismatch {
+    row,
+    col,
+    expected : expected_element.clone(),
+    got : got_element.clone(),
+    core : owner(distribution, row, col, expected.len(), expected[0].len()),
+    count : 1 + differing.count(),
+  })
+}
+
+/// Core which holds element (`row`, `col`) of a `rows` x `cols` matrix laid
+/// out with `distribution`
+fn owner(distribution : &Distribution, row : usize, col : usize, rows : usize, cols : usize) -> (usize, usize) {
+  (0..distribution.rows())
+    .flat_map(|i| (0..distribution.cols()).map(move |j| (i, j)))
+    .find(|&(i, j)| {
+      let (row_indices, col_indices) = distribution.indices(i, j, rows, cols);
+      row_indices.contains(&row) && col_indices.contains(&col)
+    })
+    .expect("every element is held by a core")
+}
+
+/// What `MatMul::parallel_square` computes, on the host
+pub fn serial_square<T : Semiring>(matrix_a : &Matrix<T>, outer_iterations : usize) -> Matrix<T> {
+  let mut matrix_c = T::zero_matrix(matrix_a.len(), matrix_a.len());
+  let mut factor = matrix_a.clone();
+  for _ in 0..outer_iterations {
+    matrix_c = serial_matmul(&factor, &factor, &matrix_c);
+    factor = matrix_c.clone();
+  }
+  matrix_c
+}
+
+/// `matrix_a` to the power `power`, on the host
+pub fn serial_pow<T : Semiring>(matrix_a : &Matrix<T>, power : usize) -> Matrix<T> {
+  let zero = T::zero_matrix(matrix_a.len(), matrix_a.len());
+  (0..power).fold(T::identity_matrix(matrix_a.len()), |product, _| serial_matmul(&product, matrix_a, &zero))
+}
+
+/// Closure of `matrix_a`, which already has `one` added on the diagonal, by
+/// squaring it on the host until it stops changing
+pub fn serial_closure<T : Semiring + PartialEq>(matrix_a : &Matrix<T>) -> Matrix<T> {
+  let zero = T::zero_matrix(matrix_a.len(), matrix_a.len());
+  let mut closure = matrix_a.clone();
+  loop {
+    let squared = serial_matmul(&closure, &closure, &zero);
+    if squared == closure {
+      return closure;
+    }
+    closure = squared;
+  }
+}
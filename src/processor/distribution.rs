@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+
+use crate::broadcast::Sendable;
+use crate::types::Matrix;
+
+use super::{Core, CoreSpeeds, get_balanced_submatrices_dim, taurus::TaurusOption};
+
+/// Strategy for laying a matrix out over the cores of a grid
+#[derive(Clone, Debug, PartialEq)]
+pub enum Distribution {
+  /// Each core holds one contiguous block, sized by the speed of the cores
+  Block(CoreSpeeds),
+  /// ScaLAPACK-style 2D block-cyclic distribution. The matrix is cut into
+  /// `block_rows` x `block_cols` blocks which are dealt out to the grid
+  /// cyclically along both axes.
+  BlockCyclic { rows : usize, cols : usize, block_rows : usize, block_cols : usize },
+  /// Another distribution with its blocks rotated around the grid, as used by
+  /// the initial alignment of Cannon's algorithm
+  Skewed(Box<Distribution>, Skew),
+  /// Another distribution over the first layer of a stack of layers, see
+  /// `taurus::link::Torus`. Cores on the other layers hold nothing.
+  Stacked(Box<Distribution>, usize),
+  /// The matrix cut into `2^levels` x `2^levels` equal blocks, each laid out
+  /// by another distribution. A core's pieces of the blocks are tiled in the
+  /// same order, so every quadrant of its local matrix is its part of the
+  /// same quadrant of the matrix.
+  Quadrants(Box<Distribution>, usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Skew {
+  /// Row `i` of the grid is rotated left by `i`, so core (`i`, `j`) holds the
+  /// block of (`i`, `i + j`)
+  Left,
+  /// Column `j` of the grid is rotated up by `j`, so core (`i`, `j`) holds the
+  /// block of (`i + j`, `j`)
+  Up,
+}
+
+impl Distribution {
+  pub fn block(rows : usize, cols : usize) -> Self {
+    Distribution::Block(CoreSpeeds::uniform(rows, cols))
+  }
+
+  /// Panics on an empty grid or empty blocks. `MatMul` checks that the grid
+  /// matches its processor.
+  pub fn block_cyclic(rows : usize, cols : usize, block_rows : usize, block_cols : usize) -> Self {
+    assert!(rows > 0 && cols > 0, "a block-cyclic distribution needs a grid, not {rows} x {cols}");
+    assert!(block_rows > 0 && block_cols > 0,
+            "a block-cyclic distribution needs blocks, not {block_rows} x {block_cols}");
+    Distribution::BlockCyclic { rows, cols, block_rows, block_cols }
+  }
+
+  pub fn skewed(distribution : Distribution, skew : Skew) -> Self {
+    Distribution::Skewed(Box::new(distribution), skew)
+  }
+
+  pub fn stacked(distribution : Distribution, layers : usize) -> Self {
+    Distribution::Stacked(Box::new(distribution), layers)
+  }
+
+  /// Splits the matrix `levels` times into quadrants laid out by
+  /// `distribution`
+  pub fn quadrants(distribution : Distribution, levels : usize) -> Self {
+    if levels == 0 {
+      distribution
+    } else {
+      Distribution::Quadrants(Box::new(distribution), levels)
+    }
+  }
+
+  /// Number of layers the grid of the distribution is stacked into
+  pub fn layers(&self) -> usize {
+    match self {
+      Distribution::Stacked(_, layers) => *layers,
+      Distribution::Skewed(distribution, _) | Distribution::Quadrants(distribution, _) => distribution.layers(),
+      _ => 1,
+    }
+  }
+
+  /// Rows and columns of the blocks a block-cyclic distribution deals out,
+  /// which split the columns of A and the rows of B alike only when square
+  pub fn cyclic_blocks(&self) -> Option<(usize, usize)> {
+    match self {
+      Distribution::BlockCyclic { block_rows, block_cols, .. } => Some((*block_rows, *block_cols)),
+      Distribution::Skewed(distribution, _) | Distribution::Stacked(distribution, _)
+        | Distribution::Quadrants(distribution, _) => distribution.cyclic_blocks(),
+      Distribution::Block(_) => None,
+    }
+  }
+
+  /// The same kind of distribution over the first of `layers` layers of the
+  /// grid, stacked so that only that layer holds the matrix
+  pub fn on_first_layer(&self, layers : usize) -> Self {
+    Distribution::stacked(self.first_layer(layers), layers)
+  }
+
+  fn first_layer(&self, layers : usize) -> Self {
+    match self {
+      Distribution::Block(speeds) => Distribution::Block(
+        CoreSpeeds::from_fn(speeds.rows / layers, speeds.cols, |row, col| speeds.speed(row, col))),
+      Distribution::BlockCyclic { rows, cols, block_rows, block_cols } =>
+        Distribution::block_cyclic(rows / layers, *cols, *block_rows, *block_cols),
+      Distribution::Skewed(distribution, skew) => Distribution::skewed(distribution.first_layer(layers), *skew),
+      Distribution::Stacked(distribution, _) => (**distribution).clone(),
+      Distribution::Quadrants(distribution, levels) => Distribution::quadrants(distribution.first_layer(layers), *levels),
+    }
+  }
+
+  pub fn rows(&self) -> usize {
+    match self {
+      Distribution::Block(speeds) => speeds.rows,
+      Distribution::BlockCyclic { rows, .. } => *rows,
+      Distribution::Skewed(distribution, _) => distribution.rows(),
+      Distribution::Stacked(distribution, layers) => distribution.rows() * layers,
+      Distribution::Quadrants(distribution, _) => distribution.rows(),
+    }
+  }
+
+  pub fn cols(&self) -> usize {
+    match self {
+      Distribution::Block(speeds) => speeds.cols,
+      Distribution::BlockCyclic { cols, .. } => *cols,
+      Distribution::Skewed(distribution, _) => distribution.cols(),
+      Distribution::Stacked(distribution, _) => distribution.cols(),
+      Distribution::Quadrants(distribution, _) => distribution.cols(),
+    }
+  }
+
+  /// This function returns the global row and column indices of the elements
+  /// of a `matrix_rows` x `matrix_cols` matrix held by the core at
+  /// (`row`, `col`)
+  ///
+  /// # Returns
+  /// Returns the row indices and column indices in the order they appear in
+  /// the local matrix of the core
+  pub fn indices(&self, row : usize, col : usize, matrix_rows : usize, matrix_cols : usize) -> (Vec<usize>, Vec<usize>) {
+    match self {
+      Distribution::Block(speeds) => {
+        let dim = get_balanced_submatrices_dim(speeds, matrix_rows, matrix_cols)[row * speeds.cols + col];
+        ((dim.start_row..dim.start_row + dim.height).collect(),
+         (dim.start_col..dim.start_col + dim.width).collect())
+      },
+      Distribution::BlockCyclic { rows, cols, block_rows, block_cols } => {
+        (cyclic_indices(*rows, row, *block_rows, matrix_rows),
+         cyclic_indices(*cols, col, *block_cols, matrix_cols))
+      },
+      Distribution::Skewed(distribution, Skew::Left) => {
+        distribution.indices(row, (row + col) % distribution.cols(), matrix_rows, matrix_cols)
+      },
+      Distribution::Skewed(distribution, Skew::Up) => {
+        distribution.indices((row + col) % distribution.rows(), col, matrix_rows, matrix_cols)
+      },
+      Distribution::Stacked(distribution, _) => {
+        distribution.indices_or_empty(row, col, matrix_rows, matrix_cols)
+      },
+      Distribution::Quadrants(distribution, levels) => {
+        let blocks = 1 << levels;
+        assert!(matrix_rows.is_multiple_of(blocks) && matrix_cols.is_multiple_of(blocks),
+          "a {matrix_rows} x {matrix_cols} matrix cannot be split into {blocks} x {blocks} blocks");
+        let (block_rows, block_cols) = (matrix_rows / blocks, matrix_cols / blocks);
+        let (row_indices, col_indices) = distribution.indices(row, col, block_rows, block_cols);
+        (tiled_indices(&row_indices, blocks, block_rows), tiled_indices(&col_indices, blocks, block_cols))
+      },
+    }
+  }
+
+  /// Like `indices`, but cores outside the grid of the distribution hold
+  /// nothing
+  fn indices_or_empty(&self, row : usize, col : usize, matrix_rows : usize, matrix_cols : usize) -> (Vec<usize>, Vec<usize>) {
+    if row < self.rows() && col < self.cols() {
+      self.indices(row, col, matrix_rows, matrix_cols)
+    } else {
+      (Vec::new(), Vec::new())
+    }
+  }
+
+  /// Splits `matrix` into the local matrix of every core in row-major order
+  pub fn scatter<K : Clone>(&self, matrix : &Matrix<K>) -> Vec<Matrix<K>> {
+    let matrix_rows = matrix.len();
+    let matrix_cols = matrix[0].len();
+    (0..self.rows()).flat_map(|row| (0..self.cols()).map(move |col| (row, col)))
+      .map(|(row, col)| {
+        let (row_indices, col_indices) = self.indices(row, col, matrix_rows, matrix_cols);
+        row_indices.iter()
+          .map(|&i| col_indices.iter().map(|&j| matrix[i][j].clone()).collect())
+          .collect()
+      })
+      .collect()
+  }
+
+  /// Writes the local matrix of the core at (`row`, `col`) back into `matrix`
+  pub fn gather<K : Clone>(&self, row : usize, col : usize, local : &Matrix<K>, matrix : &mut Matrix<K>) {
+    let matrix_rows = matrix.len();
+    let matrix_cols = matrix[0].len();
+    let (row_indices, col_indices) = self.indices(row, col, matrix_rows, matrix_cols);
+    for (local_row, &i) in local.iter().zip(row_indices.iter()) {
+      for (element, &j) in local_row.iter().zip(col_indices.iter()) {
+        matrix[i][j] = element.clone();
+      }
+    }
+  }
+}
+
+/// Plan for moving a `matrix_rows` x `matrix_cols` matrix from one
+/// distribution to another over a `grid_rows` x `grid_cols` processor.
+///
+/// Both distributions must fit inside the processor grid. Every core runs
+/// `run` with the block it holds under `from` and gets back the block it holds
+/// under `to`. Elements are routed with point-to-point messages, first along
+/// the rows of the torus to their destination column and then along the
+/// columns to their destination row, so the cost of the move shows up in any
+/// probe wrapping the cores. The columns are walked as one ring, so neither
+/// distribution may be stacked into layers and the processor must be a
+/// single torus.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Redistribution {
+  from : Distribution,
+  to : Distribution,
+  matrix_rows : usize,
+  matrix_cols : usize,
+  grid_rows : usize,
+  grid_cols : usize,
+}
+
+impl Redistribution {
+  pub fn new(from : Distribution, to : Distribution, matrix_rows : usize, matrix_cols : usize,
+             grid_rows : usize, grid_cols : usize) -> Self {
+    assert!(from.rows() <= grid_rows && from.cols() <= grid_cols, "source distribution does not fit the processor");
+    assert!(to.rows() <= grid_rows && to.cols() <= grid_cols, "target distribution does not fit the processor");
+    assert!(from.layers() == 1 && to.layers() == 1,
+            "cannot redistribute between layouts on {} and {} layers, only within a single torus", from.layers(), to.layers());
+    Redistribution { from, to, matrix_rows, matrix_cols, grid_rows, grid_cols }
+  }
+
+  pub fn from(&self) -> &Distribution {
+    &self.from
+  }
+
+  pub fn to(&self) -> &Distribution {
+    &self.to
+  }
+
+  fn source_indices(&self, row : usize, col : usize) -> (Vec<usize>, Vec<usize>) {
+    self.from.indices_or_empty(row, col, self.matrix_rows, self.matrix_cols)
+  }
+
+  fn target_indices(&self, row : usize, col : usize) -> (Vec<usize>, Vec<usize>) {
+    self.to.indices_or_empty(row, col, self.matrix_rows, self.matrix_cols)
+  }
+
+  /// Moves `block`, the local matrix of `core_info` under `from`, to the
+  /// cores which hold it under `to`
+  ///
+  /// # Returns
+  /// Returns the local matrix of `core_info` under `to`, which is empty for
+  /// cores outside its grid
+  pub fn run<T, CoreType>(&self, block : Matrix<T>, core_info : &mut CoreType) -> Matrix<T>
+  where T : Sendable,
+        CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
+    let (row, col) = (core_info.row(), core_info.col());
+    let (source_rows, source_cols) = self.source_indices(row, col);
+
+    // Split the block by destination column, each part stacking the rows
+    // bound for every destination row in order
+    let mut by_col : Vec<Option<Matrix<T>>> = (0..self.grid_cols)
+      .map(|target_col| {
+        let mut part : Matrix<T> = Vec::new();
+        for target_row in 0..self.grid_rows {
+          let (target_rows, target_cols) = self.target_indices(target_row, target_col);
+          let cols = local_positions(&source_cols, &target_cols);
+          for i in local_positions(&source_rows, &target_rows) {
+            part.push(cols.iter().map(|&j| block[i][j].clone()).collect());
+          }
+        }
+        Some(part)
+      })
+      .collect();
+
+    let from_col = shift_all(&mut by_col, col, self.grid_cols, core_info, &TaurusOption::LEFT, &TaurusOption::RIGHT);
+
+    // Each part now sits in its destination column. Regroup by destination
+    // row, stacking the parts from every source column in order.
+    let mut by_row : Vec<Option<Matrix<T>>> = (0..self.grid_rows).map(|_| Some(Vec::new())).collect();
+    for (source_col, part) in from_col.into_iter().enumerate() {
+      let rows_held = self.source_indices(row, source_col).0;
+      let mut rows = part.into_iter();
+      for (target_row, stacked) in by_row.iter_mut().enumerate() {
+        let height = local_positions(&rows_held, &self.target_indices(target_row, col).0).len();
+        stacked.as_mut().unwrap().extend(rows.by_ref().take(height));
+      }
+    }
+
+    let from_row = shift_all(&mut by_row, row, self.grid_rows, core_info, &TaurusOption::UP, &TaurusOption::DOWN);
+
+    // Place the received elements using the indices of their source cores
+    let (target_rows, target_cols) = self.target_indices(row, col);
+    let row_position : HashMap<usize, usize> = target_rows.iter().enumerate().map(|(i, &r)| (r, i)).collect();
+    let col_position : HashMap<usize, usize> = target_cols.iter().enumerate().map(|(j, &c)| (c, j)).collect();
+    let mut local : Vec<Vec<Option<T>>> = target_rows.iter().map(|_| target_cols.iter().map(|_| None).collect()).collect();
+    for (source_row, part) in from_row.into_iter().enumerate() {
+      let mut rows = part.into_iter();
+      for source_col in 0..self.grid_cols {
+        let (rows_held, cols_held) = self.source_indices(source_row, source_col);
+        let cols_shared : Vec<usize> = cols_held.into_iter().filter(|c| col_position.contains_key(c)).collect();
+        for global_row in rows_held.into_iter().filter(|r| row_position.contains_key(r)) {
+          let values = rows.next().unwrap();
+          for (value, global_col) in values.into_iter().zip(cols_shared.iter()) {
+            local[row_position[&global_row]][col_position[global_col]] = Some(value);
+          }
+        }
+      }
+    }
+
+    local.into_iter()
+      .map(|r| r.into_iter().map(|element| element.expect("element missing after redistribution")).collect())
+      .collect()
+  }
+}
+
+/// Positions in `held` of the indices which also appear in `wanted`
+fn local_positions(held : &[usize], wanted : &[usize]) -> Vec<usize> {
+  held.iter().enumerate()
+    .filter(|(_, index)| wanted.contains(index))
+    .map(|(position, _)| position)
+    .collect()
+}
+
+/// Personalised all-to-all along one ring of the torus. `parts[k]` is bound
+/// for the core at position `k` of the ring. In round `s` every core sends the
+/// part bound for the core `s` positions before it, which travels `s` hops.
+///
+/// # Returns
+/// Returns the parts received indexed by the position of their source
+fn shift_all<T, CoreType>(parts : &mut [Option<Matrix<T>>], position : usize, length : usize,
+                          core_info : &mut CoreType, send : &TaurusOption, recv : &TaurusOption) -> Vec<Matrix<T>>
+where T : Sendable,
+      CoreType : Core<Matrix<T>, ChannelOption = TaurusOption> {
+  let mut received : Vec<Option<Matrix<T>>> = (0..length).map(|_| None).collect();
+  received[position] = parts[position].take();
+  for shift in 1..length {
+    let mut part = parts[(position + length - shift) % length].take().unwrap();
+    for _ in 0..shift {
+      core_info.send(part, send);
+      part = core_info.recv(recv);
+    }
+    received[(position + shift) % length] = Some(part);
+  }
+  received.into_iter().map(Option::unwrap).collect()
+}
+
+/// `indices` of one block repeated for each of `blocks` blocks of
+/// `block_length` along an axis
+fn tiled_indices(indices : &[usize], blocks : usize, block_length : usize) -> Vec<usize> {
+  (0..blocks).flat_map(|block| indices.iter().map(move |index| block * block_length + index)).collect()
+}
+
+/// Indices along an axis of length `matrix_length` held by processor `index`
+/// of `processor_length` when blocks of `block_length` are dealt out cyclically
+fn cyclic_indices(processor_length : usize, index : usize, block_length : usize, matrix_length : usize) -> Vec<usize> {
+  (0..matrix_length)
+    .filter(|i| (i / block_length) % processor_length == index)
+    .collect()
+}
@@ -0,0 +1,167 @@
+use std::{fmt, sync::{Arc, Mutex}};
+
+use crate::broadcast::Sendable;
+
+use super::{NetworkBuilder, CoreSpeeds};
+
+/// How the memory capacity of each core is chosen, in bytes
+#[derive(Clone)]
+pub enum MemoryCapacity {
+  Uniform(usize),
+  Function(fn(usize, usize) -> usize),
+}
+
+impl MemoryCapacity {
+  pub fn capacity(&self, row : usize, col : usize) -> usize {
+    match self {
+      MemoryCapacity::Uniform(capacity) => *capacity,
+      MemoryCapacity::Function(f) => f(row, col),
+    }
+  }
+}
+
+/// A core ran out of memory
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryError {
+  pub row : usize,
+  pub col : usize,
+  pub requested : usize,
+  pub available : usize,
+  pub capacity : usize,
+}
+
+impl fmt::Display for MemoryError {
+  fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Core {} {} out of memory: requested {} bytes with {} of {} available",
+           self.row, self.col, self.requested, self.available, self.capacity)
+  }
+}
+
+struct LedgerState {
+  rows : usize,
+  cols : usize,
+  capacity : Vec<usize>,
+  held : Vec<usize>,
+}
+
+/// Shared record of the bytes held by every core of a grid, counting both
+/// the blocks a core reports through `Core::allocate` and the messages
+/// buffered on its channels which it has not received yet
+pub struct MemoryLedger {
+  state : Arc<Mutex<LedgerState>>,
+}
+
+impl Clone for MemoryLedger {
+  fn clone(&self) -> Self {
+    MemoryLedger { state : Arc::clone(&self.state) }
+  }
+}
+
+impl MemoryLedger {
+  pub fn new(rows : usize, cols : usize, capacity : &MemoryCapacity) -> Self {
+    MemoryLedger {
+      state : Arc::new(Mutex::new(LedgerState {
+        rows,
+        cols,
+        capacity : (0..rows * cols).map(|index| capacity.capacity(index / cols, index % cols)).collect(),
+        held : vec![0; rows * cols],
+      })),
+    }
+  }
+
+  pub fn rows(&self) -> usize {
+    self.state.lock().unwrap().rows
+  }
+
+  pub fn cols(&self) -> usize {
+    self.state.lock().unwrap().cols
+  }
+
+  /// Charges `bytes` to the core at (`row`, `col`)
+  ///
+  /// # Returns
+  /// Returns an error, leaving the core unchanged, if it does not have
+  /// `bytes` available
+  pub fn allocate(&self, row : usize, col : usize, bytes : usize) -> Result<(), MemoryError> {
+    let mut state = self.state.lock().unwrap();
+    let index = row * state.cols + col;
+    let capacity = state.capacity[index];
+    let available = capacity.saturating_sub(state.held[index]);
+    if bytes > available {
+      return Err(MemoryError { row, col, requested : bytes, available, capacity });
+    }
+    state.held[index] += bytes;
+    Ok(())
+  }
+
+  pub fn free(&self, row : usize, col : usize, bytes : usize) {
+    let mut state = self.state.lock().unwrap();
+    let index = row * state.cols + col;
+    state.held[index] = state.held[index].saturating_sub(bytes);
+  }
+
+  /// Bytes currently charged to the core at (`row`, `col`)
+  pub fn held(&self, row : usize, col : usize) -> usize {
+    let state = self.state.lock().unwrap();
+    state.held[row * state.cols + col]
+  }
+}
+
+/// Cores which can be given a memory capacity through a `MemoryLedger`
+pub trait MemoryLimited {
+  fn limit_memory(&mut self, ledger : MemoryLedger);
+}
+
+/// Wraps another `NetworkBuilder` so that each of its cores has a memory
+/// capacity. A core whose buffered messages plus reported blocks would exceed
+/// its capacity panics with the row, column, requested and available bytes.
+#[derive(Clone)]
+pub struct MemoryLimitNetworkBuilder<B> {
+  networkbuilder : B,
+  capacity : MemoryCapacity,
+}
+
+impl<B> MemoryLimitNetworkBuilder<B> {
+  /// Gives every core `capacity` bytes
+  pub fn new(networkbuilder : B, capacity : usize) -> Self {
+    MemoryLimitNetworkBuilder { networkbuilder, capacity : MemoryCapacity::Uniform(capacity) }
+  }
+
+  /// Gives the core at (`row`, `col`) `f(row, col)` bytes
+  pub fn with_capacity_fn(mut self, f : fn(usize, usize) -> usize) -> Self {
+    self.capacity = MemoryCapacity::Function(f);
+    self
+  }
+}
+
+impl<T, B> NetworkBuilder<T> for MemoryLimitNetworkBuilder<B>
+  where T : Sendable,
+        B : NetworkBuilder<T>,
+        B::CoreType : MemoryLimited,
+{
+  type CoreType = B::CoreType;
+
+  fn build(&self, rows: usize, cols : usize) -> Vec<Self::CoreType> {
+    let ledger = MemoryLedger::new(rows, cols, &self.capacity);
+    let mut cores = self.networkbuilder.build(rows, cols);
+    for core in cores.iter_mut() {
+      core.limit_memory(ledger.clone());
+    }
+    cores
+  }
+
+  fn core_speeds(&self, rows: usize, cols : usize) -> CoreSpeeds {
+    self.networkbuilder.core_speeds(rows, cols)
+  }
+
+  fn layers(&self) -> usize {
+    self.networkbuilder.layers()
+  }
+}
+
+/// Charges `bytes` to a core, panicking with a report if it does not fit
+pub(crate) fn charge(ledger : &MemoryLedger, row : usize, col : usize, bytes : usize) {
+  if let Err(error) = ledger.allocate(row, col, bytes) {
+    panic!("{}", error);
+  }
+}
@@ -1,16 +1,24 @@
 use crate::broadcast::Sendable;
-use std::{time::Duration, thread::{JoinHandle, self}, marker::PhantomData};
+use crate::types::Matrix;
+use std::{time::Duration, thread::{JoinHandle, self}, marker::PhantomData, panic};
 
 pub mod taurus;
 pub mod probe;
+pub mod watchdog;
+pub mod memory;
+pub mod distribution;
 
-use self::probe::{Prober, CoreDebug};
+use self::probe::{Prober, CoreDebug, TimeBreakdown};
+use self::probe::{critical::CriticalPath, stack::Trace};
+use self::taurus::{TaurusOption, link::Torus};
 
 
 pub trait TimedCore<T : Sendable> : Core<T> {
   fn blank() -> Self;
-  fn transmission_time<S>(&self, item : &S ,ch_option : &Self::ChannelOption) -> Duration;
-  fn latency(&self) -> Duration;
+  fn transmission_time<S : Sendable>(&self, item : &S ,ch_option : &Self::ChannelOption) -> Duration;
+  fn latency(&self, ch_option : &Self::ChannelOption) -> Duration;
+  /// Relative compute speed of the core, used to scale measured compute time
+  fn speed(&self) -> f64;
 }
 
 pub trait Core<T : Sendable> {
@@ -20,14 +28,98 @@ pub trait Core<T : Sendable> {
   fn col(&self) -> usize;
   fn send(&mut self, data : T, ch_option : &Self::ChannelOption);
   fn recv(&mut self, ch_option : &Self::ChannelOption) -> T;
+  /// Called just before the core is handed to the thread which runs on it
+  fn start(&mut self) {}
+  /// Called on the thread once the work given to the core has finished
+  fn stop(&mut self) {}
+  /// Records that the core now holds `bytes` more data, for cores and probes
+  /// which model memory
+  fn allocate(&mut self, _bytes : usize) {}
+  /// Records that the core no longer holds `bytes` of data
+  fn free(&mut self, _bytes : usize) {}
 }
 
 
 pub trait NetworkBuilder<T:Sendable> {
   type CoreType: Core<T>;
   fn build(&self, rows: usize, cols : usize) -> Vec<Self::CoreType>;
+  fn core_speeds(&self, rows: usize, cols : usize) -> CoreSpeeds {
+    CoreSpeeds::uniform(rows, cols)
+  }
+  /// Number of layers the grid is stacked into, see `taurus::link::Torus`
+  fn layers(&self) -> usize {
+    1
+  }
 }
 
+/// Relative compute speed of every core in a grid. A core with speed 2.0
+/// completes the same work in half the time of a core with speed 1.0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoreSpeeds {
+  pub rows : usize,
+  pub cols : usize,
+  speeds : Vec<f64>,
+}
+
+impl CoreSpeeds {
+  pub fn uniform(rows : usize, cols : usize) -> Self {
+    CoreSpeeds { rows, cols, speeds : vec![1.0; rows * cols] }
+  }
+
+  /// Panics unless every speed is positive and finite, which the submatrix
+  /// sizes are proportional to
+  pub fn from_fn(rows : usize, cols : usize, f : impl Fn(usize, usize) -> f64) -> Self {
+    let speeds = (0..rows).flat_map(|row| (0..cols).map(move |col| (row, col)))
+      .map(|(row, col)| {
+        let speed = f(row, col);
+        assert!(speed.is_finite() && speed > 0.0, "core {row} {col} has speed {speed}, which is not positive");
+        speed
+      })
+      .collect();
+    CoreSpeeds { rows, cols, speeds }
+  }
+
+  /// Panics if `speeds` is empty or ragged, or holds a speed `from_fn`
+  /// rejects
+  pub fn from_matrix(speeds : &Matrix<f64>) -> Self {
+    let cols = speeds.first().map_or(0, Vec::len);
+    assert!(cols > 0, "the speed matrix is empty");
+    assert!(speeds.iter().all(|row| row.len() == cols), "the rows of the speed matrix differ in length");
+    CoreSpeeds::from_fn(speeds.len(), cols, |row, col| speeds[row][col])
+  }
+
+  pub fn speed(&self, row : usize, col : usize) -> f64 {
+    self.speeds[row * self.cols + col]
+  }
+
+  /// Returns the weights used to size the rows and columns of submatrices.
+  ///
+  /// On a square grid the same weights are used along both axes so that the
+  /// inner dimensions of the blocks of A and B still line up; the weight of
+  /// index `k` is the total speed of processor row `k` and processor column
+  /// `k`. Otherwise each axis is weighted by the total speed of its rows or
+  /// columns respectively.
+  pub fn axis_weights(&self) -> (Vec<f64>, Vec<f64>) {
+    let row_sums : Vec<f64> = (0..self.rows)
+      .map(|row| (0..self.cols).map(|col| self.speed(row, col)).sum())
+      .collect();
+    let col_sums : Vec<f64> = (0..self.cols)
+      .map(|col| (0..self.rows).map(|row| self.speed(row, col)).sum())
+      .collect();
+
+    if self.rows == self.cols {
+      let weights : Vec<f64> = row_sums.iter().zip(col_sums.iter()).map(|(r, c)| r + c).collect();
+      (weights.clone(), weights)
+    } else {
+      (row_sums, col_sums)
+    }
+  }
+}
+
+
+/// A thread started on the core at (`row`, `col`), which hands back its
+/// result and the core when joined
+type Running<H, C> = (usize, usize, JoinHandle<(H, C)>);
 
 pub struct Processor<H, T, CoreType> 
   where H : Sendable + 'static,
@@ -37,10 +129,61 @@ pub struct Processor<H, T, CoreType>
   pub rows : usize,
   pub cols : usize,
   cores : Vec<CoreType>,
-  handles : Vec<JoinHandle<H>>,
+  speeds : CoreSpeeds,
+  layers : usize,
+  handles : Vec<Running<H, CoreType>>,
   phantom : PhantomData<T>,
 }
 
+/// Results of a run indexed by the grid coordinate of the core that produced
+/// them. Cores which were never given work have no entry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<H> {
+  pub rows : usize,
+  pub cols : usize,
+  cells : Vec<Option<H>>,
+}
+
+impl<H> Grid<H> {
+  pub fn new(rows : usize, cols : usize) -> Self {
+    Grid { rows, cols, cells : (0..rows * cols).map(|_| None).collect() }
+  }
+
+  pub fn get(&self, row : usize, col : usize) -> Option<&H> {
+    if row >= self.rows || col >= self.cols {
+      return None;
+    }
+    self.cells[row * self.cols + col].as_ref()
+  }
+
+  pub fn insert(&mut self, row : usize, col : usize, item : H) {
+    self.cells[row * self.cols + col] = Some(item);
+  }
+
+  pub fn take(&mut self, row : usize, col : usize) -> Option<H> {
+    if row >= self.rows || col >= self.cols {
+      return None;
+    }
+    self.cells[row * self.cols + col].take()
+  }
+
+  /// Iterates over the populated cells in row-major order as `(row, col, item)`
+  pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &H)> {
+    let cols = self.cols;
+    self.cells.iter().enumerate()
+      .filter_map(move |(index, cell)| cell.as_ref().map(|item| (index / cols, index % cols, item)))
+  }
+
+  /// Consumes the grid, yielding the populated cells in row-major order as
+  /// `(row, col, item)`
+  pub fn into_cells(self) -> impl Iterator<Item = (usize, usize, H)> {
+    let cols = self.cols;
+    self.cells.into_iter().enumerate()
+      .filter_map(move |(index, cell)| cell.map(|item| (index / cols, index % cols, item)))
+  }
+}
+
+
 impl<H, T, CoreType> Processor<H, T, CoreType> 
   where H : Sendable + 'static,
         T : Sendable + 'static,
@@ -48,33 +191,110 @@ impl<H, T, CoreType> Processor<H, T, CoreType>
         {
   pub fn new(rows : usize, cols : usize, networkbuilder : impl NetworkBuilder<T, CoreType = CoreType>)
     -> Self {
-    Processor {rows , cols, handles : Vec::new(), cores : networkbuilder.build(rows, cols), phantom : PhantomData}
+    Processor {rows , cols, handles : Vec::new(), cores : networkbuilder.build(rows, cols),
+               speeds : networkbuilder.core_speeds(rows, cols), layers : networkbuilder.layers(),
+               phantom : PhantomData}
+  }
+
+  pub fn speeds(&self) -> &CoreSpeeds {
+    &self.speeds
+  }
+
+  /// Shape of the network the cores are connected by
+  pub fn torus(&self) -> Torus {
+    Torus::stacked(self.rows, self.cols, self.layers)
+  }
+
+  /// Removes the core at grid coordinate (`row`, `col`) so that it can be
+  /// handed to a thread. Returns `None` if that core has already been used.
+  fn take_core(&mut self, row : usize, col : usize) -> Option<CoreType> {
+    let index = self.cores.iter().position(|core| core.row() == row && core.col() == col)?;
+    Some(self.cores.remove(index))
+  }
+
+  /// Removes the first core in row-major order that has not been used yet
+  fn next_core(&mut self) -> Option<CoreType> {
+    let (row, col) = self.cores.iter().map(|core| (core.row(), core.col())).min()?;
+    self.take_core(row, col)
+  }
+
+  fn spawn<F> (&mut self, mut core_info : CoreType, f: F)
+  where
+      F: FnOnce(&mut CoreType) -> H + Send + 'static,
+  {
+    let (row, col) = (core_info.row(), core_info.col());
+    core_info.start();
+    let handle = thread::spawn(move || {
+      let result = f(&mut core_info);
+      core_info.stop();
+      (result, core_info)
+    });
+    self.handles.push((row, col, handle));
+  }
+
+  /// Waits for a thread to finish and returns its core to the processor so
+  /// that it can be given more work
+  fn join(&mut self, handle : JoinHandle<(H, CoreType)>) -> H {
+    let (result, core_info) = handle.join().unwrap_or_else(|e| panic::resume_unwind(e));
+    self.cores.push(core_info);
+    result
   }
 
   pub fn run_core<F> (&mut self, f: F) 
   where
       F: FnOnce(&mut CoreType) -> H + Send + 'static,
   {
-    match self.cores.pop() {
+    match self.next_core() {
       None => (),
-      Some(mut core_info) => {
-        let handle = thread::spawn(move || {
-          let result = f(&mut core_info);
-          result
-        });
-        self.handles.push(handle);
+      Some(core_info) => self.spawn(core_info, f),
+    }
+  }
+
+  /// Runs `f` on the core at grid coordinate (`row`, `col`). Does nothing if
+  /// that core has already been given work.
+  pub fn run_core_at<F> (&mut self, row : usize, col : usize, f: F) 
+  where
+      F: FnOnce(&mut CoreType) -> H + Send + 'static,
+  {
+    match self.take_core(row, col) {
+      None => (),
+      Some(core_info) => self.spawn(core_info, f),
+    }
+  }
+
+  /// Runs a closure on every core of the grid. `make` is called once per
+  /// coordinate in row-major order and returns the closure to run there.
+  pub fn run_all<M, F> (&mut self, mut make : M) 
+  where
+      M: FnMut(usize, usize) -> F,
+      F: FnOnce(&mut CoreType) -> H + Send + 'static,
+  {
+    for row in 0..self.rows {
+      for col in 0..self.cols {
+        let f = make(row, col);
+        self.run_core_at(row, col, f);
       }
     }
   }
 
   pub fn collect_results (&mut self) -> Vec<H> {
     let mut results = Vec::new();
-    while !self.handles.is_empty() {
-      let handle = self.handles.pop().unwrap();
-      results.push(handle.join().unwrap());
+    while let Some((_, _, handle)) = self.handles.pop() {
+      results.push(self.join(handle));
     }
     results
   }
+
+  /// Waits for every running core and returns their results indexed by the
+  /// coordinate of the core which produced them
+  pub fn collect_grid (&mut self) -> Grid<H> {
+    let mut grid = Grid::new(self.rows, self.cols);
+    while let Some((row, col, handle)) = self.handles.pop() {
+      let result = self.join(handle);
+      grid.insert(row, col, result);
+    }
+    grid
+  }
 }
 
 
@@ -110,20 +330,67 @@ impl<D, H, U, CoreType> ProbeProcessor<D, H, U, CoreType>
     self.proc.cols
   }
 
+  pub fn speeds(&self) -> &CoreSpeeds {
+    self.proc.speeds()
+  }
+
+  pub fn torus(&self) -> Torus {
+    self.proc.torus()
+  }
+
+  fn spawn<F,P> (&mut self, mut core_info : CoreType, f: F)
+  where
+      P : Prober<D,U,CoreType>,
+      F: FnOnce(&mut P) -> H + Send + 'static,
+  {
+    let (row, col) = (core_info.row(), core_info.col());
+    core_info.start();
+    let handle = thread::spawn(move || {
+      let mut probe = P::new(core_info);
+      let result = f(&mut probe);
+      let (stat, mut core_info) = probe.release();
+      core_info.stop();
+      ((result, stat), core_info)
+    });
+    self.proc.handles.push((row, col, handle));
+  }
+
   pub fn run_core<F,P> (&mut self, f: F) 
   where
       P : Prober<D,U,CoreType>,
       F: FnOnce(&mut P) -> H + Send + 'static,
   {
-    match self.proc.cores.pop() {
+    match self.proc.next_core() {
       None => (),
-      Some(core_info) => {
-        let handle = thread::spawn(move || {
-          let mut probe = P::new(core_info);
-          let result = f(&mut probe);
-          (result, probe.extract_stat())
-        });
-        self.proc.handles.push(handle);
+      Some(core_info) => self.spawn(core_info, f),
+    }
+  }
+
+  /// Runs `f` on the probed core at grid coordinate (`row`, `col`). Does
+  /// nothing if that core has already been given work.
+  pub fn run_core_at<F,P> (&mut self, row : usize, col : usize, f: F) 
+  where
+      P : Prober<D,U,CoreType>,
+      F: FnOnce(&mut P) -> H + Send + 'static,
+  {
+    match self.proc.take_core(row, col) {
+      None => (),
+      Some(core_info) => self.spawn(core_info, f),
+    }
+  }
+
+  /// Runs a closure on every probed core of the grid. `make` is called once
+  /// per coordinate in row-major order and returns the closure to run there.
+  pub fn run_all<M,F,P> (&mut self, mut make : M) 
+  where
+      P : Prober<D,U,CoreType>,
+      M: FnMut(usize, usize) -> F,
+      F: FnOnce(&mut P) -> H + Send + 'static,
+  {
+    for row in 0..self.rows() {
+      for col in 0..self.cols() {
+        let f = make(row, col);
+        self.run_core_at(row, col, f);
       }
     }
   }
@@ -138,11 +405,103 @@ impl<D, H, U, CoreType> ProbeProcessor<D, H, U, CoreType>
     data
   }
 
+  /// Waits for every running core and returns their results indexed by the
+  /// coordinate of the core which produced them
+  pub fn collect_grid (&mut self) -> Grid<H> {
+    let results = self.proc.collect_grid();
+    let mut grid = Grid::new(results.rows, results.cols);
+    for (row, col, (result, debug)) in results.into_cells() {
+      self.debugs.push(debug);
+      grid.insert(row, col, result);
+    }
+    grid
+  }
+
   pub fn debug_stats(&self) -> &Vec<CoreDebug<D>> {
     &self.debugs
   }
 }
 
+/// A grid of cores that closures taking `&mut C` can be run on, so code
+/// driving the cores is written once for a `Processor`, whose closures take
+/// its cores, and a `ProbeProcessor`, whose closures take any prober of them
+pub trait Runner<H, C> {
+  fn rows(&self) -> usize;
+  fn cols(&self) -> usize;
+  fn speeds(&self) -> &CoreSpeeds;
+  /// Runs a closure on every core of the grid. `make` is called once per
+  /// coordinate in row-major order and returns the closure to run there.
+  fn run_all<M, F> (&mut self, make : M)
+  where
+      M: FnMut(usize, usize) -> F,
+      F: FnOnce(&mut C) -> H + Send + 'static;
+  /// Waits for every running core and returns their results indexed by the
+  /// coordinate of the core which produced them
+  fn collect_grid (&mut self) -> Grid<H>;
+}
+
+impl<H, T, CoreType> Runner<H, CoreType> for Processor<H, T, CoreType> 
+  where H : Sendable + 'static,
+        T : Sendable + 'static,
+        CoreType : Core<T> + Send + 'static,
+        {
+  fn rows(&self) -> usize {
+    self.rows
+  }
+
+  fn cols(&self) -> usize {
+    self.cols
+  }
+
+  fn speeds(&self) -> &CoreSpeeds {
+    Processor::speeds(self)
+  }
+
+  fn run_all<M, F> (&mut self, make : M)
+  where
+      M: FnMut(usize, usize) -> F,
+      F: FnOnce(&mut CoreType) -> H + Send + 'static,
+  {
+    Processor::run_all(self, make)
+  }
+
+  fn collect_grid (&mut self) -> Grid<H> {
+    Processor::collect_grid(self)
+  }
+}
+
+impl<D, H, U, CoreType, P> Runner<H, P> for ProbeProcessor<D, H, U, CoreType> 
+  where D : Sendable + 'static,
+        H : Sendable + 'static,
+        U : Sendable + 'static,
+        CoreType : Core<U> + Send + 'static,
+        P : Prober<D, U, CoreType>,
+        {
+  fn rows(&self) -> usize {
+    ProbeProcessor::rows(self)
+  }
+
+  fn cols(&self) -> usize {
+    ProbeProcessor::cols(self)
+  }
+
+  fn speeds(&self) -> &CoreSpeeds {
+    ProbeProcessor::speeds(self)
+  }
+
+  fn run_all<M, F> (&mut self, make : M)
+  where
+      M: FnMut(usize, usize) -> F,
+      F: FnOnce(&mut P) -> H + Send + 'static,
+  {
+    ProbeProcessor::run_all(self, make)
+  }
+
+  fn collect_grid (&mut self) -> Grid<H> {
+    ProbeProcessor::collect_grid(self)
+  }
+}
+
 impl<H, T, CoreType> ProbeProcessor<Duration, H, T, CoreType> 
   where H : Sendable + 'static,
         T : Sendable + 'static,
@@ -161,6 +520,62 @@ impl<H, T, CoreType> ProbeProcessor<Duration, H, T, CoreType>
   }
 }
 
+impl<H, T, CoreType> ProbeProcessor<TimeBreakdown, H, T, CoreType> 
+  where H : Sendable + 'static,
+        T : Sendable + 'static,
+        CoreType : TimedCore<T> + Send,
+        {
+
+  pub fn display_breakdown (&self) {
+    for debug in &self.debugs {
+      println!("Core {} {} compute: {}µs send: {}µs wait: {}µs messages: {} sent {} received bytes: {} sent {} received",
+               debug.row, debug.col, debug.stat.compute.as_micros(), debug.stat.send.as_micros(),
+               debug.stat.wait.as_micros(), debug.stat.messages.messages_sent, debug.stat.messages.messages_received,
+               debug.stat.messages.bytes_sent, debug.stat.messages.bytes_received);
+    }
+  }
+
+  /// Sum of the breakdowns of every core
+  pub fn total_breakdown (&self) -> TimeBreakdown {
+    self.debugs.iter().fold(TimeBreakdown::default(), |acc, debug| acc + debug.stat)
+  }
+
+  /// Breakdown of the core which took the longest, which bounds the run time
+  pub fn max_breakdown (&self) -> Option<TimeBreakdown> {
+    self.debugs.iter().map(|debug| debug.stat).max_by_key(TimeBreakdown::total)
+  }
+}
+
+impl<H, T, CoreType> ProbeProcessor<(Duration, Trace<TaurusOption>), H, T, CoreType> 
+  where H : Sendable + 'static,
+        T : Sendable + 'static,
+        CoreType : Core<T> + Send + 'static,
+        {
+
+  /// Chain of work and messages which determined the time of the slowest
+  /// core of the runs collected so far
+  pub fn critical_path (&self) -> CriticalPath {
+    CriticalPath::from_debug(&self.debugs, self.torus())
+  }
+}
+
+impl<H, T, CoreType> ProbeProcessor<(Duration, usize), H, T, CoreType> 
+  where H : Sendable + 'static,
+        T : Sendable + 'static,
+        CoreType : Core<T> + Send + 'static,
+        {
+
+  /// Multiply-adds done by every core of the runs collected so far
+  pub fn total_operations (&self) -> usize {
+    self.debugs.iter().map(|debug| debug.stat.1).sum()
+  }
+
+  /// Multiply-adds done by the busiest core, which bounds the compute time
+  pub fn max_operations (&self) -> Option<usize> {
+    self.debugs.iter().map(|debug| debug.stat.1).max()
+  }
+}
+
 #[derive(Copy,Clone,Debug, PartialEq)]
 pub struct SubmatrixDim {
   pub start_row : usize,
@@ -192,10 +607,34 @@ fn get_submatrices_dim_along_axis(processor_length : usize, matrix_length : usiz
   submatrix_dimensions
 }
 
-pub fn get_submatrices_dim(processor_rows : usize, processor_cols : usize, matrix_rows : usize, matrix_cols : usize) -> Vec<SubmatrixDim> {
-  let dim_along_y = get_submatrices_dim_along_axis(processor_rows, matrix_rows);
-  let dim_along_x = get_submatrices_dim_along_axis(processor_cols, matrix_cols);
+/// This function returns a Vec containing the dimensions of the submatrices to 
+/// be assigned to each processor along a given axis, where each processor
+/// receives a share of the matrix proportional to its weight
+///
+/// # Arguemnts
+/// * `weights` - Weight of each processor along a given axis
+/// * `matrix_length` - Length of matrix along a given axis
+///
+/// # Returns
+/// Returns the Vec<usize> of length `weights.len()`. Rows or columns left
+/// over after rounding down go to the processors with the largest
+/// remainders, with ties going to the first processors
+fn get_weighted_dim_along_axis(weights : &[f64], matrix_length : usize) -> Vec<usize> {
+  let total : f64 = weights.iter().sum();
+  let ideal : Vec<f64> = weights.iter().map(|w| matrix_length as f64 * w / total).collect();
+  let mut submatrix_dimensions : Vec<usize> = ideal.iter().map(|x| x.floor() as usize).collect();
+  let assigned : usize = submatrix_dimensions.iter().sum();
+
+  let mut order : Vec<usize> = (0..weights.len()).collect();
+  order.sort_by(|&i, &j| (ideal[j] - ideal[j].floor()).total_cmp(&(ideal[i] - ideal[i].floor())));
+  for &index in order.iter().take(matrix_length - assigned) {
+    submatrix_dimensions[index] += 1;
+  }
+
+  submatrix_dimensions
+}
 
+fn get_submatrices_dim_from_axes(dim_along_y : &[usize], dim_along_x : &[usize]) -> Vec<SubmatrixDim> {
   dim_along_y.iter().fold((0, Vec::new()), |(start_row, mut result), &height| {
     dim_along_x.iter().fold(0, |start_col, &width| {
       result.push(SubmatrixDim {
@@ -210,6 +649,23 @@ pub fn get_submatrices_dim(processor_rows : usize, processor_cols : usize, matri
     }).1
 }
 
+pub fn get_submatrices_dim(processor_rows : usize, processor_cols : usize, matrix_rows : usize, matrix_cols : usize) -> Vec<SubmatrixDim> {
+  let dim_along_y = get_submatrices_dim_along_axis(processor_rows, matrix_rows);
+  let dim_along_x = get_submatrices_dim_along_axis(processor_cols, matrix_cols);
+
+  get_submatrices_dim_from_axes(&dim_along_y, &dim_along_x)
+}
+
+/// Load-balanced version of `get_submatrices_dim` which gives faster cores
+/// larger blocks. With uniform speeds it matches `get_submatrices_dim`.
+pub fn get_balanced_submatrices_dim(speeds : &CoreSpeeds, matrix_rows : usize, matrix_cols : usize) -> Vec<SubmatrixDim> {
+  let (row_weights, col_weights) = speeds.axis_weights();
+  let dim_along_y = get_weighted_dim_along_axis(&row_weights, matrix_rows);
+  let dim_along_x = get_weighted_dim_along_axis(&col_weights, matrix_cols);
+
+  get_submatrices_dim_from_axes(&dim_along_y, &dim_along_x)
+}
+
 fn get_matrix_slices<K:Clone>(matrix : &Vec<Vec<K>>, dims : &Vec<SubmatrixDim>) -> Vec<Vec<Vec<K>>> {
   dims.iter().map(|&dim| 
     matrix.iter().skip(dim.start_row).take(dim.height)
@@ -227,6 +683,12 @@ pub fn get_submatrices<K: Clone>(processor_rows : usize, processor_cols : usize,
   get_matrix_slices(matrix, &submatrices_dim)
 }
 
+pub fn get_balanced_submatrices<K: Clone>(speeds : &CoreSpeeds, matrix : &Vec<Vec<K>>) -> Vec<Vec<Vec<K>>> {
+  let submatrices_dim = get_balanced_submatrices_dim(speeds, matrix.len(), matrix[0].len());
+
+  get_matrix_slices(matrix, &submatrices_dim)
+}
+
 
 #[cfg(test)]
 mod tests;
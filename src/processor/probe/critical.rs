@@ -0,0 +1,187 @@
+use std::{fmt, time::Duration};
+
+use crate::processor::taurus::{TaurusOption, link::Torus};
+
+use super::CoreDebug;
+use super::stack::{Trace, TraceEntry, TraceEvent};
+
+/// Grid coordinate of a core as `(row, col)`
+pub type Coord = (usize, usize);
+
+/// One step of a critical path
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathSegment {
+  /// Time the core spent computing or being charged for sends
+  Local { row : usize, col : usize, from : Duration, to : Duration },
+  /// A message the receiver was waiting for
+  Message { from : Coord, to : Coord, channel : TaurusOption, sent : Duration, arrived : Duration },
+}
+
+impl PathSegment {
+  pub fn duration(&self) -> Duration {
+    match self {
+      PathSegment::Local { from, to, .. } => *to - *from,
+      PathSegment::Message { sent, arrived, .. } => *arrived - *sent,
+    }
+  }
+}
+
+/// The chain of local work and messages which determines the finishing time
+/// of the slowest core of a run, in the order it happened.
+///
+/// The path is found by walking back from the slowest core. Whenever a core
+/// resumed exactly when a message arrived, it was waiting on that message, so
+/// the path continues on the sender from the moment it sent it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CriticalPath {
+  pub segments : Vec<PathSegment>,
+}
+
+struct CoreTrace<'a> {
+  row : usize,
+  col : usize,
+  end : Duration,
+  trace : &'a Trace<TaurusOption>,
+}
+
+impl CriticalPath {
+  /// Computes the critical path of a run on the network `torus` from the
+  /// stats of a `StackProber` with a `Trace` layer
+  pub fn from_debug(debugs : &[CoreDebug<(Duration, Trace<TaurusOption>)>], torus : Torus) -> Self {
+    let traces : Vec<_> = debugs.iter()
+      .map(|debug| (debug.row, debug.col, debug.stat.0, &debug.stat.1))
+      .collect();
+    CriticalPath::from_traces(&traces, torus)
+  }
+
+  /// Computes the critical path from the end time and trace of every core,
+  /// given as `(row, col, end, trace)`
+  pub fn from_traces(traces : &[(usize, usize, Duration, &Trace<TaurusOption>)], torus : Torus) -> Self {
+    let cores : Vec<CoreTrace> = traces.iter()
+      .map(|&(row, col, end, trace)| CoreTrace { row, col, end, trace })
+      .collect();
+    let mut segments = Vec::new();
+    let last = match cores.iter().enumerate().max_by_key(|(_, core)| core.end) {
+      None => return CriticalPath { segments },
+      Some((index, _)) => index,
+    };
+
+    // Walk back from the end of the slowest core, only looking at trace
+    // entries before `limit`
+    let (mut current, mut limit, mut time) = (last, cores[last].trace.entries.len(), cores[last].end);
+    loop {
+      let core = &cores[current];
+      let waited = core.trace.entries[..limit].iter().enumerate().rev()
+        .find(|(_, entry)| entry.event == TraceEvent::Received && entry.at == entry.arrival && entry.at <= time);
+      let sender = waited.and_then(|(index, _)| find_send(&cores, core, index, &torus));
+
+      match (waited, sender) {
+        (Some((_, received)), Some((sender, index))) => {
+          let sent = &cores[sender].trace.entries[index];
+          segments.push(PathSegment::Local { row : core.row, col : core.col, from : received.at, to : time });
+          segments.push(PathSegment::Message {
+            from : (cores[sender].row, cores[sender].col),
+            to : (core.row, core.col),
+            channel : sent.channel,
+            sent : sent.at,
+            arrived : received.arrival,
+          });
+          current = sender;
+          limit = index;
+          time = sent.at;
+        },
+        _ => {
+          segments.push(PathSegment::Local { row : core.row, col : core.col, from : Duration::ZERO, to : time });
+          break;
+        },
+      }
+    }
+
+    segments.reverse();
+    CriticalPath { segments }
+  }
+
+  /// Length of the path, which is the finishing time of the slowest core
+  pub fn length(&self) -> Duration {
+    self.segments.iter().map(PathSegment::duration).sum()
+  }
+
+  /// Cores on the path in the order they are visited, without repeats of
+  /// consecutive visits
+  pub fn cores(&self) -> Vec<Coord> {
+    let mut cores : Vec<Coord> = Vec::new();
+    for segment in &self.segments {
+      if let PathSegment::Local { row, col, .. } = segment {
+        if cores.last() != Some(&(*row, *col)) {
+          cores.push((*row, *col));
+        }
+      }
+    }
+    cores
+  }
+
+  /// Links crossed by the path as `(from, to, channel)`
+  pub fn links(&self) -> Vec<(Coord, Coord, TaurusOption)> {
+    self.segments.iter()
+      .filter_map(|segment| match segment {
+        PathSegment::Message { from, to, channel, .. } => Some((*from, *to, *channel)),
+        PathSegment::Local { .. } => None,
+      })
+      .collect()
+  }
+}
+
+impl fmt::Display for CriticalPath {
+  fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "Critical path: {}µs", self.length().as_micros())?;
+    for segment in &self.segments {
+      match segment {
+        PathSegment::Local { row, col, from, to } =>
+          writeln!(f, "  Core {} {} local {}µs -> {}µs", row, col, from.as_micros(), to.as_micros())?,
+        PathSegment::Message { from, to, channel, sent, arrived } =>
+          writeln!(f, "  Message {} {} -> {} {} on {:?} {}µs -> {}µs",
+                   from.0, from.1, to.0, to.1, channel, sent.as_micros(), arrived.as_micros())?,
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Finds the send which delivered the message entry `received` of the trace
+/// of `receiver` records. Sends from the cores which reach `receiver` on the
+/// matching channel are candidates when they arrive at the same time. Links
+/// deliver in order, so when several arrive together the `n`th of them the
+/// receiver took is the `n`th candidate, in grid order and then in the order
+/// they were sent.
+///
+/// # Returns
+/// Returns the index of the sending core and of the send in its trace
+fn find_send(cores : &[CoreTrace], receiver : &CoreTrace, received : usize, torus : &Torus) -> Option<(usize, usize)> {
+  let TraceEntry { channel, arrival, .. } = &receiver.trace.entries[received];
+  // A message received on one side of a direct link was sent towards the
+  // opposite side by the neighbour on that side, while a broadcast may come
+  // from any core it reaches
+  let sent_on = match channel {
+    TaurusOption::LEFT => TaurusOption::RIGHT,
+    TaurusOption::RIGHT => TaurusOption::LEFT,
+    TaurusOption::UP => TaurusOption::DOWN,
+    TaurusOption::DOWN => TaurusOption::UP,
+    TaurusOption::ABOVE => TaurusOption::BELOW,
+    TaurusOption::BELOW => TaurusOption::ABOVE,
+    broadcast => *broadcast,
+  };
+  let sources = torus.destinations(receiver.row, receiver.col, channel);
+  let is_sender = |core : &CoreTrace| sources.contains(&(core.row, core.col));
+  let is_tie = |entry : &&TraceEntry<TaurusOption>| entry.channel == *channel && entry.arrival == *arrival;
+  let ties = receiver.trace.entries[..received].iter()
+    .filter(|entry| entry.event == TraceEvent::Received)
+    .filter(is_tie)
+    .count();
+
+  cores.iter().enumerate()
+    .filter(|(_, core)| is_sender(core))
+    .flat_map(|(sender, core)| core.trace.entries.iter().enumerate()
+      .filter(|(_, entry)| entry.event == TraceEvent::Sent && entry.channel == sent_on && entry.arrival == *arrival)
+      .map(move |(index, _)| (sender, index)))
+    .nth(ties)
+}
@@ -1,10 +1,15 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use std::ops::Add;
 use cpu_time::ThreadTime;
-use std::marker::PhantomData;
 
 use crate::broadcast::Sendable;
 
-use super::{Core, TimedCore};
+use super::Core;
+use stack::{Elapsed, Layer, LayerProber, MessageCount, Timing};
+
+pub mod stack;
+pub mod critical;
+pub mod ops;
 
 #[derive(Clone, Debug)]
 pub struct CoreDebug<T> {
@@ -25,34 +30,97 @@ impl<T : Clone + Sendable> CoreDebug<T> {
 
 impl<T : Sendable> Sendable for CoreDebug<T> {}
 
-pub struct ThreadTimeProbe {
+/// Source of elapsed time for a probe
+pub trait Clock {
+  fn now() -> Self;
+  fn elapsed(&self) -> Duration;
+}
+
+/// CPU time of the thread running the core, which ignores time the thread
+/// spends descheduled
+impl Clock for ThreadTime {
+  fn now() -> Self {
+    ThreadTime::now()
+  }
+
+  fn elapsed(&self) -> Duration {
+    ThreadTime::elapsed(self)
+  }
+}
+
+/// Wall-clock time, which includes time the thread spends descheduled
+impl Clock for Instant {
+  fn now() -> Self {
+    Instant::now()
+  }
+
+  fn elapsed(&self) -> Duration {
+    Instant::elapsed(self)
+  }
+}
+
+pub struct TimeProbe<C : Clock> {
   core_debug : CoreDebug<Duration>,
-  thread_time : ThreadTime,
+  clock : C,
   additional : Duration,
+  charged : Duration,
+  waited : Duration,
+  speed : f64,
 }
 
-impl ThreadTimeProbe {
+pub type ThreadTimeProbe = TimeProbe<ThreadTime>;
+pub type WallTimeProbe = TimeProbe<Instant>;
+
+impl<C : Clock> TimeProbe<C> {
   pub fn new(row : usize, col : usize) -> Self {
-    ThreadTimeProbe { 
+    TimeProbe::with_speed(row, col, 1.0)
+  }
+
+  /// Creates a probe for a core which computes `speed` times faster than the
+  /// host thread, so measured CPU time is divided by `speed`
+  pub fn with_speed(row : usize, col : usize, speed : f64) -> Self {
+    TimeProbe { 
       core_debug: CoreDebug::new(row, col, Duration::ZERO),
-      thread_time: ThreadTime::now(),
-      additional: Duration::ZERO
+      clock: C::now(),
+      additional: Duration::ZERO,
+      charged: Duration::ZERO,
+      waited: Duration::ZERO,
+      speed,
     }
   }
 
   pub fn get_curr_elapsed(&self) -> Duration {
-    self.thread_time.elapsed() + self.additional
+    self.clock.elapsed().div_f64(self.speed) + self.additional
   }
 
   pub fn update_elapsed(&mut self, outer : Duration) {
     let current = self.get_curr_elapsed();
     if current < outer {
-      self.additional += outer - current
+      self.additional += outer - current;
+      self.waited += outer - current;
     }
   }
 
   pub fn increment_time(&mut self, increment : Duration) {
     self.additional += increment;
+    self.charged += increment;
+  }
+
+  /// Time spent computing, i.e. the measured clock time scaled by the speed
+  /// of the core
+  pub fn compute_time(&self) -> Duration {
+    self.clock.elapsed().div_f64(self.speed)
+  }
+
+  /// Time added by `increment_time`
+  pub fn charged_time(&self) -> Duration {
+    self.charged
+  }
+
+  /// Time added by `update_elapsed` jumping forward to the arrival of a
+  /// message
+  pub fn waited_time(&self) -> Duration {
+    self.waited
   }
 
   pub fn end(mut self) -> CoreDebug<Duration>{
@@ -67,61 +135,83 @@ pub trait Prober<D,U, CoreType>
 {
   fn new(core : CoreType) -> Self;
 
-  fn extract_stat(self) -> CoreDebug<D>;
-}
+  /// Ends the probe, returning its statistics and the core it wrapped
+  fn release(self) -> (CoreDebug<D>, CoreType);
 
-pub struct ThreadTimeProber <T : Sendable, CoreType>
-  where T : Sendable,
-        CoreType : TimedCore<(T,Duration)>, 
-{
-  core : CoreType,
-  probe : ThreadTimeProbe,
-  phantom : PhantomData<T>,
-} 
-
-impl<T, CoreType> Core<T> for ThreadTimeProber<T, CoreType> 
-  where T : Sendable,
-        CoreType : TimedCore<(T,Duration)> 
-{
-    type ChannelOption= CoreType::ChannelOption;
+  fn extract_stat(self) -> CoreDebug<D> where Self : Sized {
+    self.release().0
+  }
+}
 
-    fn row(&self) -> usize {
-        self.core.row()
-    }
+/// Prober which measures each core with the clock `C`, charging sends for
+/// bandwidth and startup and jumping forward to the arrival time of messages
+pub type TimeProber<T, CoreType, C> = LayerProber<T, CoreType, Elapsed, C>;
+
+/// Prober measuring the CPU time of each core
+pub type ThreadTimeProber<T, CoreType> = TimeProber<T, CoreType, ThreadTime>;
+/// Prober measuring the wall-clock time of each core
+pub type WallTimeProber<T, CoreType> = TimeProber<T, CoreType, Instant>;
+
+/// Breakdown of the simulated time of a core and of the traffic it generated.
+/// `compute + send + wait` is the total time the core took.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimeBreakdown {
+  /// CPU time scaled by the speed of the core
+  pub compute : Duration,
+  /// Time charged for sending, covering bandwidth and broadcast startup
+  pub send : Duration,
+  /// Time spent waiting for messages to arrive
+  pub wait : Duration,
+  /// Traffic counted by an inner `MessageCount` layer
+  pub messages : MessageCount,
+}
 
-    fn col(&self) -> usize {
-        self.core.col()
-    }
+impl TimeBreakdown {
+  pub fn total(&self) -> Duration {
+    self.compute + self.send + self.wait
+  }
+}
 
-    fn send(&mut self, data : T, ch_option : &Self::ChannelOption) {
-      let comm_cost = self.core.transmission_time(&data, &ch_option);
-      self.probe.increment_time(comm_cost);
-      let recv_time =  self.probe.get_curr_elapsed() + self.core.latency();
-      self.core.send((data,recv_time), &ch_option)
-    }
+impl Add for TimeBreakdown {
+  type Output = TimeBreakdown;
 
-    fn recv(&mut self, ch_option : &Self::ChannelOption) -> T {
-      let (data, recv_time) = self.core.recv(ch_option);
-      self.probe.update_elapsed(recv_time);
-      data
+  fn add(self, other : TimeBreakdown) -> TimeBreakdown {
+    TimeBreakdown {
+      compute : self.compute + other.compute,
+      send : self.send + other.send,
+      wait : self.wait + other.wait,
+      messages : self.messages + other.messages,
     }
+  }
 }
 
+impl Sendable for TimeBreakdown {}
 
-impl<T, CoreType> Prober<Duration, (T,Duration), CoreType> for ThreadTimeProber<T, CoreType> 
-  where T : Sendable,
-        CoreType : TimedCore<(T,Duration)> 
-{
-    fn new(core : CoreType) -> Self {
-      let row = core.row();
-      let col = core.col();
-        ThreadTimeProber { core, probe: ThreadTimeProbe::new(row, col), phantom: PhantomData}
-    }
+/// Counts messages through its `MessageCount`, and splits the time of the
+/// core once it is released
+impl<T : Sendable, O> Layer<T, O> for TimeBreakdown {
+  type Stat = TimeBreakdown;
 
-    fn extract_stat(self) -> CoreDebug<Duration> {
-        self.probe.end()
-    }
+  fn new(_ : usize, _ : usize) -> Self {
+    TimeBreakdown::default()
+  }
+
+  fn sent(&mut self, data : &T, channel : &O, at : Duration, arrival : Duration) {
+    self.messages.sent(data, channel, at, arrival);
+  }
+
+  fn received(&mut self, data : &T, channel : &O, at : Duration, arrival : Duration) {
+    self.messages.received(data, channel, at, arrival);
+  }
+
+  fn finish(self, timing : &Timing) -> Self::Stat {
+    TimeBreakdown { compute : timing.compute, send : timing.charged, wait : timing.waited, ..self }
+  }
 }
 
+/// Prober with the same timing as `ThreadTimeProber` which also reports where
+/// the time went and how many messages and bytes the core sent and received
+pub type BreakdownProber<T, CoreType> = LayerProber<T, CoreType, TimeBreakdown>;
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,37 @@
+use std::cell::Cell;
+use super::stack::{Layer, Timing};
+
+thread_local! {
+  static OPERATIONS : Cell<usize> = const { Cell::new(0) };
+}
+
+/// Records `count` multiply-adds done by the current thread. Every core runs
+/// on its own thread, so kernels can report their work without access to the
+/// core.
+pub fn record_operations(count : usize) {
+  OPERATIONS.with(|operations| operations.set(operations.get() + count));
+}
+
+/// Multiply-adds recorded by the current thread so far
+pub fn operations() -> usize {
+  OPERATIONS.with(|operations| operations.get())
+}
+
+/// Number of multiply-adds (`Semiring::mul` calls) a core
+/// did, which measures compute independently of the host hardware
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OperationCount {
+  start : usize,
+}
+
+impl<T, O> Layer<T, O> for OperationCount {
+  type Stat = usize;
+
+  fn new(_ : usize, _ : usize) -> Self {
+    OperationCount { start : operations() }
+  }
+
+  fn finish(self, _ : &Timing) -> Self::Stat {
+    operations() - self.start
+  }
+}
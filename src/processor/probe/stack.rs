@@ -0,0 +1,365 @@
+use std::time::Duration;
+use std::marker::PhantomData;
+use std::fmt::Debug;
+use std::ops::Add;
+use cpu_time::ThreadTime;
+
+use crate::broadcast::Sendable;
+use crate::processor::{Core, TimedCore};
+
+use super::{Clock, CoreDebug, Prober, TimeProbe};
+
+/// A measurement which can be stacked on top of the timing of a
+/// `StackProber`. Layers observe the messages of a core along with the
+/// simulated time at which they were sent or received, but leave the
+/// messages themselves alone.
+pub trait Layer<T, O> {
+  type Stat : Sendable;
+
+  fn new(row : usize, col : usize) -> Self;
+
+  /// Called after `data` has been sent, `at` being the simulated time once
+  /// the send has been charged and `arrival` the time it reaches the
+  /// receiver
+  fn sent(&mut self, _data : &T, _ch_option : &O, _at : Duration, _arrival : Duration) {}
+
+  /// Called after `data` has been received, `arrival` being the simulated
+  /// time it arrived and `at` the time of the core once it has it, which is
+  /// later if the core was busy when it arrived
+  fn received(&mut self, _data : &T, _ch_option : &O, _at : Duration, _arrival : Duration) {}
+
+  /// Called when the core reports holding `bytes` more data
+  fn allocated(&mut self, _bytes : usize, _at : Duration) {}
+
+  /// Called when the core reports dropping `bytes` of data
+  fn freed(&mut self, _bytes : usize, _at : Duration) {}
+
+  /// Called when the core is released, with the times its clock measured
+  fn finish(self, timing : &Timing) -> Self::Stat;
+}
+
+/// Times measured for a core over its run, handed to every layer when the
+/// core is released
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timing {
+  /// Simulated time of the core at the end of its run
+  pub elapsed : Duration,
+  /// CPU time scaled by the speed of the core
+  pub compute : Duration,
+  /// Time charged for sending, covering bandwidth and broadcast startup
+  pub charged : Duration,
+  /// Time spent waiting for messages to arrive
+  pub waited : Duration,
+}
+
+/// The simulated time of the core, the stat of the plain time probers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Elapsed;
+
+impl<T, O> Layer<T, O> for Elapsed {
+  type Stat = Duration;
+
+  fn new(_ : usize, _ : usize) -> Self {
+    Elapsed
+  }
+
+  fn finish(self, timing : &Timing) -> Self::Stat {
+    timing.elapsed
+  }
+}
+
+/// The empty stack, measuring nothing beyond time
+impl<T, O> Layer<T, O> for () {
+  type Stat = ();
+
+  fn new(_ : usize, _ : usize) -> Self {}
+
+  fn finish(self, _ : &Timing) -> Self::Stat {}
+}
+
+/// Two layers stacked on top of each other, reporting both their stats
+impl<T, O, A, B> Layer<T, O> for (A, B)
+  where A : Layer<T, O>,
+        B : Layer<T, O>,
+{
+  type Stat = (A::Stat, B::Stat);
+
+  fn new(row : usize, col : usize) -> Self {
+    (A::new(row, col), B::new(row, col))
+  }
+
+  fn sent(&mut self, data : &T, ch_option : &O, at : Duration, arrival : Duration) {
+    self.0.sent(data, ch_option, at, arrival);
+    self.1.sent(data, ch_option, at, arrival);
+  }
+
+  fn received(&mut self, data : &T, ch_option : &O, at : Duration, arrival : Duration) {
+    self.0.received(data, ch_option, at, arrival);
+    self.1.received(data, ch_option, at, arrival);
+  }
+
+  fn allocated(&mut self, bytes : usize, at : Duration) {
+    self.0.allocated(bytes, at);
+    self.1.allocated(bytes, at);
+  }
+
+  fn freed(&mut self, bytes : usize, at : Duration) {
+    self.0.freed(bytes, at);
+    self.1.freed(bytes, at);
+  }
+
+  fn finish(self, timing : &Timing) -> Self::Stat {
+    (self.0.finish(timing), self.1.finish(timing))
+  }
+}
+
+/// Number and size of the messages sent and received by a core
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MessageCount {
+  pub messages_sent : usize,
+  pub messages_received : usize,
+  pub bytes_sent : usize,
+  pub bytes_received : usize,
+}
+
+impl Add for MessageCount {
+  type Output = MessageCount;
+
+  fn add(self, other : MessageCount) -> MessageCount {
+    MessageCount {
+      messages_sent : self.messages_sent + other.messages_sent,
+      messages_received : self.messages_received + other.messages_received,
+      bytes_sent : self.bytes_sent + other.bytes_sent,
+      bytes_received : self.bytes_received + other.bytes_received,
+    }
+  }
+}
+
+impl Sendable for MessageCount {}
+
+impl<T : Sendable, O> Layer<T, O> for MessageCount {
+  type Stat = MessageCount;
+
+  fn new(_ : usize, _ : usize) -> Self {
+    MessageCount::default()
+  }
+
+  fn sent(&mut self, data : &T, _ : &O, _ : Duration, _ : Duration) {
+    self.messages_sent += 1;
+    self.bytes_sent += data.message_size();
+  }
+
+  fn received(&mut self, data : &T, _ : &O, _ : Duration, _ : Duration) {
+    self.messages_received += 1;
+    self.bytes_received += data.message_size();
+  }
+
+  fn finish(self, _ : &Timing) -> Self::Stat {
+    self
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+  Sent,
+  Received,
+}
+
+/// A message seen by a core, with the simulated time at which it left or
+/// arrived
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEntry<O> {
+  pub event : TraceEvent,
+  pub channel : O,
+  pub at : Duration,
+  /// Time the message reaches its receiver, shared by both ends of a message
+  pub arrival : Duration,
+  pub bytes : usize,
+}
+
+/// Every message sent and received by a core, in the order they happened
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trace<O> {
+  pub entries : Vec<TraceEntry<O>>,
+}
+
+impl<O : Clone + Debug + Send> Sendable for Trace<O> {}
+
+impl<T : Sendable, O : Clone + Debug + Send> Layer<T, O> for Trace<O> {
+  type Stat = Trace<O>;
+
+  fn new(_ : usize, _ : usize) -> Self {
+    Trace { entries : Vec::new() }
+  }
+
+  fn sent(&mut self, data : &T, ch_option : &O, at : Duration, arrival : Duration) {
+    self.entries.push(TraceEntry { event : TraceEvent::Sent, channel : ch_option.clone(), at, arrival, bytes : data.message_size() });
+  }
+
+  fn received(&mut self, data : &T, ch_option : &O, at : Duration, arrival : Duration) {
+    self.entries.push(TraceEntry { event : TraceEvent::Received, channel : ch_option.clone(), at, arrival, bytes : data.message_size() });
+  }
+
+  fn finish(self, _ : &Timing) -> Self::Stat {
+    self
+  }
+}
+
+/// Bytes of data held by a core over the simulated time of its run, as
+/// reported through `Core::allocate` and `Core::free`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemoryFootprint {
+  pub current : usize,
+  pub peak : usize,
+  /// Time-weighted average of the bytes held
+  pub average : f64,
+  held_seconds : f64,
+  last : Duration,
+}
+
+impl MemoryFootprint {
+  fn advance(&mut self, at : Duration) {
+    let at = at.max(self.last);
+    self.held_seconds += self.current as f64 * (at - self.last).as_secs_f64();
+    self.last = at;
+  }
+}
+
+impl Sendable for MemoryFootprint {}
+
+impl<T, O> Layer<T, O> for MemoryFootprint {
+  type Stat = MemoryFootprint;
+
+  fn new(_ : usize, _ : usize) -> Self {
+    MemoryFootprint::default()
+  }
+
+  fn allocated(&mut self, bytes : usize, at : Duration) {
+    self.advance(at);
+    self.current += bytes;
+    self.peak = self.peak.max(self.current);
+  }
+
+  fn freed(&mut self, bytes : usize, at : Duration) {
+    self.advance(at);
+    self.current = self.current.saturating_sub(bytes);
+  }
+
+  fn finish(mut self, timing : &Timing) -> Self::Stat {
+    self.advance(timing.elapsed);
+    if !self.last.is_zero() {
+      self.average = self.held_seconds / self.last.as_secs_f64();
+    }
+    self
+  }
+}
+
+/// Prober which times a core with the clock `C`, charging sends for
+/// bandwidth and startup and jumping forward to the arrival time of
+/// messages, and reports whatever its layers `L` measure. Every time prober
+/// is one of these with different layers, so new measurements only need a
+/// `Layer` implementation.
+pub struct LayerProber <T, CoreType, L, C = ThreadTime>
+  where T : Sendable,
+        CoreType : TimedCore<(T,Duration)>,
+        L : Layer<T, CoreType::ChannelOption>,
+        C : Clock,
+{
+  core : CoreType,
+  pub(super) probe : TimeProbe<C>,
+  layers : L,
+  phantom : PhantomData<T>,
+}
+
+/// Prober timing a core like `ThreadTimeProber`, with the layers `L` stacked
+/// on top. The stat of each core is its elapsed time alongside the stats of
+/// the layers.
+pub type StackProber<T, CoreType, L, C = ThreadTime> = LayerProber<T, CoreType, (Elapsed, L), C>;
+
+impl<T, CoreType, L, C> LayerProber<T, CoreType, L, C>
+  where T : Sendable,
+        CoreType : TimedCore<(T,Duration)>,
+        L : Layer<T, CoreType::ChannelOption>,
+        C : Clock,
+{
+  pub fn layers(&self) -> &L {
+    &self.layers
+  }
+}
+
+impl<T, CoreType, L, C> Core<T> for LayerProber<T, CoreType, L, C>
+  where T : Sendable,
+        CoreType : TimedCore<(T,Duration)>,
+        L : Layer<T, CoreType::ChannelOption>,
+        C : Clock,
+{
+    type ChannelOption = CoreType::ChannelOption;
+
+    fn row(&self) -> usize {
+        self.core.row()
+    }
+
+    fn col(&self) -> usize {
+        self.core.col()
+    }
+
+    fn send(&mut self, data : T, ch_option : &Self::ChannelOption) {
+      let comm_cost = self.core.transmission_time(&data, ch_option);
+      self.probe.increment_time(comm_cost);
+      let sent_time = self.probe.get_curr_elapsed();
+      let arrival = sent_time + self.core.latency(ch_option);
+      self.layers.sent(&data, ch_option, sent_time, arrival);
+      self.core.send((data, arrival), ch_option)
+    }
+
+    fn recv(&mut self, ch_option : &Self::ChannelOption) -> T {
+      let (data, recv_time) = self.core.recv(ch_option);
+      let resumed = self.probe.get_curr_elapsed().max(recv_time);
+      self.probe.update_elapsed(recv_time);
+      self.layers.received(&data, ch_option, resumed, recv_time);
+      data
+    }
+
+    fn start(&mut self) {
+      self.core.start()
+    }
+
+    fn allocate(&mut self, bytes : usize) {
+      self.layers.allocated(bytes, self.probe.get_curr_elapsed());
+      self.core.allocate(bytes)
+    }
+
+    fn free(&mut self, bytes : usize) {
+      self.layers.freed(bytes, self.probe.get_curr_elapsed());
+      self.core.free(bytes)
+    }
+}
+
+impl<T, CoreType, L, C> Prober<L::Stat, (T,Duration), CoreType> for LayerProber<T, CoreType, L, C>
+  where T : Sendable,
+        CoreType : TimedCore<(T,Duration)>,
+        L : Layer<T, CoreType::ChannelOption>,
+        C : Clock,
+{
+    fn new(core : CoreType) -> Self {
+      let row = core.row();
+      let col = core.col();
+      let speed = core.speed();
+      LayerProber {
+        core,
+        probe : TimeProbe::with_speed(row, col, speed),
+        layers : L::new(row, col),
+        phantom : PhantomData,
+      }
+    }
+
+    fn release(self) -> (CoreDebug<L::Stat>, CoreType) {
+      let timing = Timing {
+        elapsed : self.probe.get_curr_elapsed(),
+        compute : self.probe.compute_time(),
+        charged : self.probe.charged_time(),
+        waited : self.probe.waited_time(),
+      };
+      (CoreDebug::new(self.core.row(), self.core.col(), self.layers.finish(&timing)), self.core)
+    }
+}
@@ -1,6 +1,7 @@
 use super::*;
 use super::super::*;
 use super::super::taurus::*;
+use super::super::taurus::link::Link;
 use std::{thread::sleep, time::Instant};
 
 #[test]
@@ -275,3 +276,311 @@ fn test_comm_info_startup_3cores(){
   assert!(debug[1].stat.as_millis() > 5400);
   assert!(debug[1].stat.as_millis() < 5600);
 }
+
+#[test]
+fn test_core_debug_time_scaled_by_speed(){
+  let network_builder = TimeTaurusNetworkBuilder::new(0, 1, 0)
+    .with_speed_fn(|row, _| if row == 1 { 2.0 } else { 1.0 });
+  let mut processor : ProbeProcessor <Duration, (),(i32,Duration), TimedTaurusCore<(i32,Duration)>> = 
+    ProbeProcessor::new(2,2, network_builder);
+  
+  let busy = move |core_info: &mut ThreadTimeProber<i32, TimedTaurusCore<(i32,Duration)>>| {
+    let t = ThreadTime::now();
+    while t.elapsed() < Duration::new(0,500000000) {
+      continue
+    }
+    dbg!(&core_info.probe.get_curr_elapsed().as_millis());
+  };
+
+  processor.run_core_at(0, 0, busy);
+  processor.run_core_at(1, 0, busy);
+  
+  let grid = processor.collect_grid();
+  assert_eq!(grid.iter().count(), 2);
+  let debug = processor.debug_stats();
+  let slow = debug.iter().find(|d| d.row == 0).unwrap();
+  let fast = debug.iter().find(|d| d.row == 1).unwrap();
+  
+  dbg!(&slow.stat.as_millis());
+  dbg!(&fast.stat.as_millis());
+
+  assert!(slow.stat.as_millis() > 450);
+  assert!(slow.stat.as_millis() < 550);
+  assert!(fast.stat.as_millis() > 200);
+  assert!(fast.stat.as_millis() < 300);
+}
+
+#[test]
+fn test_comm_info_wrap_around_latency(){
+  let network_builder = TimeTaurusNetworkBuilder::new(0, 1, 0)
+    .with_wrap_around_links(Link::new(0, 1000000000), Link::new(200000000, 1000000000));
+  let mut processor : ProbeProcessor <Duration, (),(i32,Duration), TimedTaurusCore<(i32,Duration)>> = 
+    ProbeProcessor::new(3,3, network_builder);
+
+  processor.run_core_at(0, 0, |core_info: &mut ThreadTimeProber<i32, TimedTaurusCore<(i32,Duration)>>| {
+    core_info.send(1, &TaurusOption::LEFT);
+    core_info.recv(&TaurusOption::RIGHT);
+  });
+  processor.run_core_at(0, 1, |core_info: &mut ThreadTimeProber<i32, TimedTaurusCore<(i32,Duration)>>| {
+    core_info.send(1, &TaurusOption::LEFT);
+  });
+  processor.run_core_at(0, 2, |core_info: &mut ThreadTimeProber<i32, TimedTaurusCore<(i32,Duration)>>| {
+    core_info.recv(&TaurusOption::RIGHT);
+  });
+  
+  processor.collect_results();
+  let debug = processor.debug_stats();
+  let local = debug.iter().find(|d| d.col == 0).unwrap();
+  let wrapped = debug.iter().find(|d| d.col == 2).unwrap();
+
+  dbg!(&local.stat.as_millis());
+  dbg!(&wrapped.stat.as_millis());
+
+  assert!(local.stat.as_millis() < 50);
+  assert!(wrapped.stat.as_millis() > 180);
+  assert!(wrapped.stat.as_millis() < 250);
+}
+
+#[test]
+fn test_comm_info_tiled_broadcast(){
+  let network_builder = TimeTaurusNetworkBuilder::new(0, 1, 0)
+    .with_tiles(1, 2, Link::new(0, 1000000000), Link::new(200000000, 1000000000));
+  let mut processor : ProbeProcessor <Duration, (),(i32,Duration), TimedTaurusCore<(i32,Duration)>> = 
+    ProbeProcessor::new(2,2, network_builder);
+
+  processor.run_core_at(0, 0, |core_info: &mut ThreadTimeProber<i32, TimedTaurusCore<(i32,Duration)>>| {
+    core_info.send(1, &TaurusOption::ROW);
+    core_info.send(1, &TaurusOption::COL);
+  });
+  processor.run_core_at(0, 1, |core_info: &mut ThreadTimeProber<i32, TimedTaurusCore<(i32,Duration)>>| {
+    core_info.recv(&TaurusOption::ROW);
+  });
+  processor.run_core_at(1, 0, |core_info: &mut ThreadTimeProber<i32, TimedTaurusCore<(i32,Duration)>>| {
+    core_info.recv(&TaurusOption::COL);
+  });
+  
+  processor.collect_results();
+  let debug = processor.debug_stats();
+  let same_tile = debug.iter().find(|d| d.row == 0 && d.col == 1).unwrap();
+  let other_tile = debug.iter().find(|d| d.row == 1).unwrap();
+
+  dbg!(&same_tile.stat.as_millis());
+  dbg!(&other_tile.stat.as_millis());
+
+  assert!(same_tile.stat.as_millis() < 50);
+  assert!(other_tile.stat.as_millis() > 180);
+  assert!(other_tile.stat.as_millis() < 250);
+}
+
+#[test]
+fn test_breakdown_counts_payload_bytes(){
+  type Block = Vec<Vec<isize>>;
+  type TimedCore = TimedTaurusCore<(Block,Duration)>;
+  let network_builder = TimeTaurusNetworkBuilder::new(0, 1000000000, 0);
+  let mut processor : ProbeProcessor <TimeBreakdown, (),(Block,Duration), TimedCore> = 
+    ProbeProcessor::new(1,2, network_builder);
+
+  // A dense 3 x 4 block, rather than the 24 bytes of its outer `Vec`
+  processor.run_core_at(0, 0, |core_info: &mut BreakdownProber<Block, TimedCore>| {
+    core_info.send(vec![vec![1; 4]; 3], &TaurusOption::LEFT);
+  });
+  processor.run_core_at(0, 1, |core_info: &mut BreakdownProber<Block, TimedCore>| {
+    core_info.recv(&TaurusOption::RIGHT);
+  });
+  processor.collect_results();
+
+  let total = processor.total_breakdown();
+  let bytes = 12 * std::mem::size_of::<isize>();
+  assert_eq!((total.messages.bytes_sent, total.messages.bytes_received), (bytes, bytes));
+}
+
+#[test]
+fn test_breakdown_separates_compute_send_and_wait(){
+  let network_builder = TimeTaurusNetworkBuilder::new(0, 4, 0);
+  let mut processor : ProbeProcessor <TimeBreakdown, (),(i32,Duration), TimedTaurusCore<(i32,Duration)>> = 
+    ProbeProcessor::new(1,2, network_builder);
+
+  processor.run_core_at(0, 0, |core_info: &mut BreakdownProber<i32, TimedTaurusCore<(i32,Duration)>>| {
+    let t = ThreadTime::now();
+    while t.elapsed() < Duration::from_millis(100) {
+      continue
+    }
+    core_info.send(1, &TaurusOption::LEFT);
+  });
+  processor.run_core_at(0, 1, |core_info: &mut BreakdownProber<i32, TimedTaurusCore<(i32,Duration)>>| {
+    core_info.recv(&TaurusOption::RIGHT);
+  });
+  
+  processor.collect_results();
+  let debug = processor.debug_stats();
+  let sender = debug.iter().find(|d| d.col == 0).unwrap().stat;
+  let receiver = debug.iter().find(|d| d.col == 1).unwrap().stat;
+
+  dbg!(&sender);
+  dbg!(&receiver);
+
+  // Sending 4 bytes at 4 bytes per second is charged one second
+  assert_eq!(sender.send, Duration::from_secs(1));
+  assert!(sender.compute.as_millis() >= 100);
+  assert!(sender.compute.as_millis() < 150);
+  assert_eq!((sender.messages.messages_sent, sender.messages.bytes_sent), (1, 4));
+  assert_eq!(receiver.send, Duration::ZERO);
+  assert!(receiver.wait.as_millis() > 1090);
+  assert!(receiver.wait.as_millis() < 1150);
+  assert_eq!((receiver.messages.messages_received, receiver.messages.bytes_received), (1, 4));
+
+  let total = processor.total_breakdown();
+  assert_eq!((total.messages.messages_sent, total.messages.messages_received), (1, 1));
+  assert_eq!(total.send, Duration::from_secs(1));
+  assert!(processor.max_breakdown().unwrap().total() >= sender.total().max(receiver.total()));
+}
+
+#[test]
+fn test_wall_time_counts_sleep(){
+  let network_builder = TimeTaurusNetworkBuilder::new(0, 1000000000, 0);
+  let mut processor : ProbeProcessor <Duration, (),(i32,Duration), TimedTaurusCore<(i32,Duration)>> = 
+    ProbeProcessor::new(1,2, network_builder);
+
+  processor.run_core_at(0, 0, |core_info: &mut WallTimeProber<i32, TimedTaurusCore<(i32,Duration)>>| {
+    sleep(Duration::from_millis(200));
+    core_info.send(1, &TaurusOption::LEFT);
+  });
+  processor.run_core_at(0, 1, |core_info: &mut WallTimeProber<i32, TimedTaurusCore<(i32,Duration)>>| {
+    core_info.recv(&TaurusOption::RIGHT);
+  });
+  
+  processor.collect_results();
+  let debug = processor.debug_stats();
+
+  dbg!(&debug[0].stat.as_millis());
+  dbg!(&debug[1].stat.as_millis());
+
+  // Unlike CPU time, wall time includes the sleep on both cores
+  assert!(debug.iter().all(|d| d.stat.as_millis() >= 200));
+  assert!(debug.iter().all(|d| d.stat.as_millis() < 300));
+}
+
+#[test]
+fn test_stacked_layers(){
+  use super::stack::*;
+  type Stacked = StackProber<i32, TimedTaurusCore<(i32,Duration)>, (MessageCount, Trace<TaurusOption>)>;
+
+  let network_builder = TimeTaurusNetworkBuilder::new(100000000, 1000000000, 0);
+  let mut processor : ProbeProcessor <(Duration, (MessageCount, Trace<TaurusOption>)), (),(i32,Duration), TimedTaurusCore<(i32,Duration)>> = 
+    ProbeProcessor::new(1,2, network_builder);
+
+  processor.run_core_at(0, 0, |core_info: &mut Stacked| {
+    core_info.send(1, &TaurusOption::LEFT);
+    core_info.send(2, &TaurusOption::LEFT);
+  });
+  processor.run_core_at(0, 1, |core_info: &mut Stacked| {
+    core_info.recv(&TaurusOption::RIGHT);
+    core_info.recv(&TaurusOption::RIGHT);
+    assert_eq!(core_info.layers().1.0.messages_received, 2);
+  });
+  
+  processor.collect_results();
+  let debug = processor.debug_stats();
+  let (_, (sender_count, sender_trace)) = &debug.iter().find(|d| d.col == 0).unwrap().stat;
+  let (receiver_time, (receiver_count, receiver_trace)) = &debug.iter().find(|d| d.col == 1).unwrap().stat;
+
+  assert_eq!((sender_count.messages_sent, sender_count.bytes_sent), (2, 8));
+  assert_eq!((receiver_count.messages_received, receiver_count.bytes_received), (2, 8));
+  assert_eq!(sender_trace.entries.len(), 2);
+  assert!(sender_trace.entries.iter().all(|entry| entry.event == TraceEvent::Sent && entry.channel == TaurusOption::LEFT));
+  assert!(receiver_trace.entries.iter().all(|entry| entry.event == TraceEvent::Received && entry.channel == TaurusOption::RIGHT));
+
+  // Messages arrive one link latency after they were sent
+  for (sent, received) in sender_trace.entries.iter().zip(receiver_trace.entries.iter()) {
+    assert!(received.at >= sent.at + Duration::from_millis(100));
+  }
+  assert!(receiver_time.as_millis() >= 100);
+  assert!(receiver_time.as_millis() < 150);
+}
+
+#[test]
+fn test_memory_footprint_average(){
+  use super::stack::*;
+  let mut footprint = <MemoryFootprint as Layer<(), TaurusOption>>::new(0, 0);
+  Layer::<(), TaurusOption>::allocated(&mut footprint, 100, Duration::ZERO);
+  Layer::<(), TaurusOption>::allocated(&mut footprint, 50, Duration::from_secs(1));
+  Layer::<(), TaurusOption>::freed(&mut footprint, 150, Duration::from_secs(2));
+  let footprint = Layer::<(), TaurusOption>::finish(footprint, &Timing { elapsed : Duration::from_secs(4), ..Timing::default() });
+
+  assert_eq!(footprint.peak, 150);
+  assert_eq!(footprint.current, 0);
+  // 100 bytes for 1s and 150 bytes for 1s over a 4s run
+  assert_eq!(footprint.average, 62.5);
+}
+
+#[test]
+fn test_critical_path_tied_arrivals(){
+  use super::stack::*;
+  use super::critical::*;
+  use crate::processor::taurus::link::Torus;
+
+  let ms = Duration::from_millis;
+  let entry = |event, at, arrival| TraceEntry { event, channel : TaurusOption::RIGHT, at : ms(at), arrival : ms(arrival), bytes : 4 };
+  let received = |at, arrival| TraceEntry { channel : TaurusOption::LEFT, ..entry(TraceEvent::Received, at, arrival) };
+  // (0,0) sends twice to (0,1), both arriving at 10ms, and in between takes
+  // a message from (0,2). (0,1) waited on the second of the two.
+  let first = Trace { entries : vec![entry(TraceEvent::Sent, 5, 10), received(5, 5), entry(TraceEvent::Sent, 5, 10)] };
+  let second = Trace { entries : vec![received(10, 10), received(10, 10)] };
+  let third = Trace { entries : vec![entry(TraceEvent::Sent, 3, 5)] };
+
+  let path = CriticalPath::from_traces(&[(0, 0, ms(5), &first), (0, 1, ms(12), &second), (0, 2, ms(3), &third)],
+                                       Torus::new(1, 3));
+
+  assert_eq!(path.length(), ms(12));
+  assert_eq!(path.cores(), vec![(0, 2), (0, 0), (0, 1)]);
+}
+
+#[test]
+fn test_critical_path_follows_waited_messages(){
+  use super::stack::*;
+  use super::critical::*;
+  type Traced = StackProber<i32, TimedTaurusCore<(i32,Duration)>, Trace<TaurusOption>>;
+
+  fn spin(millis : u64) {
+    let t = ThreadTime::now();
+    while t.elapsed() < Duration::from_millis(millis) {
+      continue
+    }
+  }
+
+  let network_builder = TimeTaurusNetworkBuilder::new(50000000, 1000000000, 0);
+  type Stat = (Duration, Trace<TaurusOption>);
+  type TimedCore = TimedTaurusCore<(i32,Duration)>;
+  let mut processor : ProbeProcessor <Stat, (),(i32,Duration), TimedCore> = 
+    ProbeProcessor::new(1,3, network_builder);
+
+  processor.run_core_at(0, 0, |core_info: &mut Traced| {
+    // Sent early, so (0,2) is never waiting on this one
+    core_info.send(0, &TaurusOption::LEFT);
+    spin(100);
+    core_info.send(1, &TaurusOption::RIGHT);
+  });
+  processor.run_core_at(0, 1, |core_info: &mut Traced| {
+    core_info.recv(&TaurusOption::LEFT);
+    spin(100);
+    core_info.send(2, &TaurusOption::RIGHT);
+  });
+  processor.run_core_at(0, 2, |core_info: &mut Traced| {
+    spin(20);
+    core_info.recv(&TaurusOption::RIGHT);
+    core_info.recv(&TaurusOption::LEFT);
+    spin(20);
+  });
+  
+  processor.collect_results();
+  let path = processor.critical_path();
+  let slowest = processor.debug_stats().iter().map(|debug| debug.stat.0).max().unwrap();
+
+  assert_eq!(path.length(), slowest);
+  assert_eq!(path.cores(), vec![(0, 0), (0, 1), (0, 2)]);
+  assert_eq!(path.links(), vec![((0, 0), (0, 1), TaurusOption::RIGHT), ((0, 1), (0, 2), TaurusOption::RIGHT)]);
+  assert!(path.segments.iter().all(|segment| match segment {
+    PathSegment::Message { sent, arrived, .. } => *arrived - *sent == Duration::from_millis(50),
+    PathSegment::Local { .. } => true,
+  }));
+}
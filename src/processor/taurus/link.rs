@@ -0,0 +1,223 @@
+use std::{time::Duration, fs::File, io::{self, BufReader}, path::Path};
+use serde::{Serialize, Deserialize};
+
+use super::TaurusOption;
+
+/// Latency and bandwidth of a single point to point link
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Link {
+  pub latency : Duration,
+  pub bandwidth : usize,
+}
+
+impl Link {
+  /// Creates a link from a latency in ns and a bandwidth in B/ns
+  pub fn new(latency : usize, bandwidth : usize) -> Self {
+    Link {
+      latency : Duration::from_nanos(latency as u64),
+      bandwidth : if bandwidth == 0 {1} else {bandwidth},
+    }
+  }
+}
+
+/// Entry of a `LinkTable` describing the outgoing link of the core at
+/// (`row`, `col`) in `direction`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LinkEntry {
+  pub row : usize,
+  pub col : usize,
+  pub direction : TaurusOption,
+  /// Latency in ns
+  pub latency : usize,
+  /// Bandwidth in B/ns
+  pub bandwidth : usize,
+}
+
+/// Per-link parameters, typically loaded from a JSON file of the form
+/// `{"links": [{"row": 0, "col": 0, "direction": "LEFT", "latency": 100, "bandwidth": 10}]}`.
+/// Links missing from the table use the defaults of the network builder.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct LinkTable {
+  pub links : Vec<LinkEntry>,
+}
+
+impl LinkTable {
+  pub fn from_json_file(path : impl AsRef<Path>) -> io::Result<Self> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
+  }
+
+  fn get(&self, row : usize, col : usize, direction : &TaurusOption) -> Option<Link> {
+    self.links.iter()
+      .rev()
+      .find(|entry| entry.row == row && entry.col == col && entry.direction == *direction)
+      .map(|entry| Link::new(entry.latency, entry.bandwidth))
+  }
+}
+
+/// How the parameters of each link are chosen when building a timed network.
+/// Unless stated otherwise the ROW, COL and FIBRE broadcasts use the defaults
+/// of the builder.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LinkModel {
+  /// Every link uses the defaults of the builder
+  Uniform,
+  /// LEFT/RIGHT links use `horizontal` and UP/DOWN links use `vertical`.
+  /// Links between layers use the defaults.
+  Directional { horizontal : Link, vertical : Link },
+  /// Links which wrap around the edge of the torus use `wrap`, all others
+  /// use `local`
+  WrapAround { local : Link, wrap : Link },
+  /// The grid is split into `tile_rows` x `tile_cols` tiles (chips). Links
+  /// between cores of the same tile use `intra` and links crossing a tile
+  /// boundary use `inter`. A broadcast uses `inter` if its row or column
+  /// spans more than one tile. Build it with `LinkModel::tiled`, which
+  /// rejects empty tiles.
+  ///
+  /// Blocks stay where the distribution puts them: block (i, j) lives on core
+  /// (i, j), so each tile holds a contiguous range of blocks and every ring
+  /// of a shift already crosses the fewest tile boundaries a ring can.
+  Tiled { tile_rows : usize, tile_cols : usize, intra : Link, inter : Link },
+  /// Links and broadcasts listed in the table use its parameters
+  Table(LinkTable),
+}
+
+/// Shape of a network of `layers` tori of `rows / layers` x `cols` cores
+/// stacked on top of each other. Layer `l` holds the rows
+/// `l * rows / layers .. (l + 1) * rows / layers` of the processor grid, and
+/// the cores at the same position of every layer form a fibre joined by the
+/// ABOVE, BELOW and FIBRE channels. A single layer is a plain 2D torus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Torus {
+  pub rows : usize,
+  pub cols : usize,
+  pub layers : usize,
+}
+
+impl Torus {
+  pub fn new(rows : usize, cols : usize) -> Self {
+    Torus::stacked(rows, cols, 1)
+  }
+
+  pub fn stacked(rows : usize, cols : usize, layers : usize) -> Self {
+    assert!(layers > 0 && rows.is_multiple_of(layers), "{rows} rows cannot be split into {layers} layers");
+    Torus { rows, cols, layers }
+  }
+
+  /// Rows of the grid in each layer
+  pub fn layer_rows(&self) -> usize {
+    self.rows / self.layers
+  }
+
+  /// Layer holding processor row `row`
+  pub fn layer(&self, row : usize) -> usize {
+    row / self.layer_rows()
+  }
+
+  /// Returns true if sending from (`row`, `col`) in `direction` crosses the
+  /// edge of the torus
+  pub fn is_wrap_around(&self, row : usize, col : usize, direction : &TaurusOption) -> bool {
+    let layer_row = row % self.layer_rows();
+    match direction {
+      TaurusOption::LEFT => col == 0,
+      TaurusOption::RIGHT => col + 1 == self.cols,
+      TaurusOption::UP => layer_row == 0,
+      TaurusOption::DOWN => layer_row + 1 == self.layer_rows(),
+      TaurusOption::ABOVE => self.layer(row) == 0,
+      TaurusOption::BELOW => self.layer(row) + 1 == self.layers,
+      TaurusOption::ROW | TaurusOption::COL | TaurusOption::FIBRE => false,
+    }
+  }
+
+  /// Returns the coordinate of the core reached by sending from (`row`,
+  /// `col`) in `direction`. Broadcasts return the sender.
+  pub fn neighbour(&self, row : usize, col : usize, direction : &TaurusOption) -> (usize, usize) {
+    let layer_rows = self.layer_rows();
+    let (layer, layer_row) = (self.layer(row), row % layer_rows);
+    match direction {
+      TaurusOption::LEFT => (row, (col + self.cols - 1) % self.cols),
+      TaurusOption::RIGHT => (row, (col + 1) % self.cols),
+      TaurusOption::UP => (layer * layer_rows + (layer_row + layer_rows - 1) % layer_rows, col),
+      TaurusOption::DOWN => (layer * layer_rows + (layer_row + 1) % layer_rows, col),
+      TaurusOption::ABOVE => ((layer + self.layers - 1) % self.layers * layer_rows + layer_row, col),
+      TaurusOption::BELOW => ((layer + 1) % self.layers * layer_rows + layer_row, col),
+      TaurusOption::ROW | TaurusOption::COL | TaurusOption::FIBRE => (row, col),
+    }
+  }
+
+  /// Number of cores a message sent in `direction` reaches: a row of the
+  /// layer for ROW, a column of the layer for COL, every layer for FIBRE
+  /// and a single neighbour otherwise
+  pub fn group_size(&self, direction : &TaurusOption) -> usize {
+    match direction {
+      TaurusOption::ROW => self.cols,
+      TaurusOption::COL => self.layer_rows(),
+      TaurusOption::FIBRE => self.layers,
+      _ => 1,
+    }
+  }
+
+  /// Coordinates of every core which receives a message sent from (`row`,
+  /// `col`) on `direction`
+  pub fn destinations(&self, row : usize, col : usize, direction : &TaurusOption) -> Vec<(usize, usize)> {
+    let layer_rows = self.layer_rows();
+    let (layer, layer_row) = (self.layer(row), row % layer_rows);
+    match direction {
+      TaurusOption::ROW => (0..self.cols).map(|col| (row, col)).collect(),
+      TaurusOption::COL => (0..layer_rows).map(|r| (layer * layer_rows + r, col)).collect(),
+      TaurusOption::FIBRE => (0..self.layers).map(|l| (l * layer_rows + layer_row, col)).collect(),
+      _ => vec![self.neighbour(row, col, direction)],
+    }
+  }
+}
+
+/// Returns true if sending from (`row`, `col`) in `direction` crosses the
+/// edge of a `rows` x `cols` torus
+pub fn is_wrap_around(row : usize, col : usize, rows : usize, cols : usize, direction : &TaurusOption) -> bool {
+  Torus::new(rows, cols).is_wrap_around(row, col, direction)
+}
+
+/// Returns the coordinate of the core reached by sending from (`row`, `col`)
+/// in `direction` on a `rows` x `cols` torus. Broadcasts return the sender.
+pub fn neighbour(row : usize, col : usize, rows : usize, cols : usize, direction : &TaurusOption) -> (usize, usize) {
+  Torus::new(rows, cols).neighbour(row, col, direction)
+}
+
+impl LinkModel {
+  /// Splits the grid into `tile_rows` x `tile_cols` tiles, see
+  /// `LinkModel::Tiled`
+  pub fn tiled(tile_rows : usize, tile_cols : usize, intra : Link, inter : Link) -> Self {
+    assert!(tile_rows > 0 && tile_cols > 0, "tiles need cores, not {tile_rows} x {tile_cols}");
+    LinkModel::Tiled { tile_rows, tile_cols, intra, inter }
+  }
+
+  pub fn link(&self, row : usize, col : usize, torus : &Torus,
+              direction : &TaurusOption, default : Link) -> Link {
+    match self {
+      LinkModel::Uniform => default,
+      LinkModel::Directional { horizontal, vertical } => match direction {
+        TaurusOption::LEFT | TaurusOption::RIGHT => *horizontal,
+        TaurusOption::UP | TaurusOption::DOWN => *vertical,
+        _ => default,
+      },
+      LinkModel::WrapAround { local, wrap } => match direction {
+        TaurusOption::ROW | TaurusOption::COL | TaurusOption::FIBRE => default,
+        _ if torus.is_wrap_around(row, col, direction) => *wrap,
+        _ => *local,
+      },
+      LinkModel::Tiled { tile_rows, tile_cols, intra, inter } => {
+        let crosses = match direction {
+          TaurusOption::ROW => torus.cols > *tile_cols,
+          TaurusOption::COL => torus.layer_rows() > *tile_rows,
+          TaurusOption::FIBRE => torus.layers > 1 && torus.rows > *tile_rows,
+          _ => {
+            let (n_row, n_col) = torus.neighbour(row, col, direction);
+            row / tile_rows != n_row / tile_rows || col / tile_cols != n_col / tile_cols
+          }
+        };
+        if crosses { *inter } else { *intra }
+      },
+      LinkModel::Table(table) => table.get(row, col, direction).unwrap_or(default),
+    }
+  }
+}
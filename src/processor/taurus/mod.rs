@@ -1,7 +1,15 @@
 use crate::broadcast::{Broadcast, Sendable, Direct, Channel};
-use std::{time::Duration, mem::size_of_val, ops::{Mul, Div}};
+use std::{time::Duration, ops::{Mul, Div}, sync::mpsc::RecvTimeoutError};
+use serde::{Serialize, Deserialize};
 
-use super::{Core, TimedCore, NetworkBuilder};
+use crate::types::Matrix;
+
+use super::{Core, TimedCore, NetworkBuilder, CoreSpeeds};
+use super::watchdog::{Watchable, Watchdog};
+use super::memory::{MemoryLimited, MemoryLedger, charge};
+
+pub mod link;
+use link::{Link, LinkModel, LinkTable, Torus};
 
 struct TaurusComm<T:Sendable>{
   left : Direct<T>,
@@ -10,6 +18,9 @@ struct TaurusComm<T:Sendable>{
   down : Direct<T>,
   row : Broadcast<T>,
   col : Broadcast<T>,
+  above : Direct<T>,
+  below : Direct<T>,
+  fibre : Broadcast<T>,
 }
 
 impl<T : Sendable> TaurusComm<T> {
@@ -20,22 +31,63 @@ impl<T : Sendable> TaurusComm<T> {
       up: Direct::empty(),
       down: Direct::empty(),
       row: Broadcast::empty(),
-      col: Broadcast::empty()
+      col: Broadcast::empty(),
+      above: Direct::empty(),
+      below: Direct::empty(),
+      fibre: Broadcast::empty(),
     }
   } 
 }
 
 pub struct TimedTaurusCore<T : Sendable> {
-  latency : Duration,
-  bandwidth : usize,
   startup : Duration,
-  broadcast_size : usize,
+  speed : f64,
+  links : [Link; 9],
+  /// Cores reached by a message sent in each direction, which a broadcast
+  /// pays one startup for each of
+  group_sizes : [usize; 9],
   core : TaurusCore<T>
 }
 
 impl<T : Sendable> TimedTaurusCore<T> {
   pub fn new(latency: Duration, bandwidth: usize, startup: Duration, broadcast_size: usize, core: TaurusCore<T>) -> Self { 
-    Self { latency, bandwidth, startup, broadcast_size, core } 
+    let link = Link { latency, bandwidth };
+    let mut group_sizes = [1; 9];
+    for direction in [TaurusOption::ROW, TaurusOption::COL, TaurusOption::FIBRE] {
+      group_sizes[Self::link_index(&direction)] = broadcast_size;
+    }
+    Self { startup, speed : 1.0, links : [link; 9], group_sizes, core } 
+  }
+
+  /// Sets the number of cores a message sent in `direction` reaches
+  pub fn with_group_size(mut self, direction : &TaurusOption, size : usize) -> Self {
+    self.group_sizes[Self::link_index(direction)] = size;
+    self
+  }
+
+  /// Overrides the parameters of the outgoing link or broadcast in `direction`
+  pub fn with_link(mut self, direction : &TaurusOption, link : Link) -> Self {
+    self.links[Self::link_index(direction)] = link;
+    self
+  }
+
+  fn link_index(direction : &TaurusOption) -> usize {
+    match direction {
+      TaurusOption::LEFT => 0,
+      TaurusOption::RIGHT => 1,
+      TaurusOption::UP => 2,
+      TaurusOption::DOWN => 3,
+      TaurusOption::ROW => 4,
+      TaurusOption::COL => 5,
+      TaurusOption::ABOVE => 6,
+      TaurusOption::BELOW => 7,
+      TaurusOption::FIBRE => 8,
+    }
+  }
+
+  pub fn with_speed(mut self, speed : f64) -> Self {
+    self.speed = speed;
+    self
   }
 } 
 
@@ -57,27 +109,62 @@ impl<T: Sendable> Core<T> for TimedTaurusCore<T> {
     fn recv(&mut self, ch_option : &Self::ChannelOption) -> T {
       self.core.recv(ch_option)
     }
+
+    fn start(&mut self) {
+      self.core.start()
+    }
+
+    fn stop(&mut self) {
+      self.core.stop()
+    }
+
+    fn allocate(&mut self, bytes : usize) {
+      self.core.allocate(bytes)
+    }
+
+    fn free(&mut self, bytes : usize) {
+      self.core.free(bytes)
+    }
+}
+
+impl<T : Sendable> Watchable<TaurusOption> for TimedTaurusCore<T> {
+  fn watch(&mut self, watchdog : Watchdog<TaurusOption>) {
+    self.core.watch(watchdog);
+  }
+}
+
+impl<T : Sendable> MemoryLimited for TimedTaurusCore<T> {
+  fn limit_memory(&mut self, ledger : MemoryLedger) {
+    self.core.limit_memory(ledger);
+  }
 }
 
 impl<T : Sendable> TimedCore<T> for TimedTaurusCore<T> {
 
-  fn transmission_time<S>(&self, item : &S ,ch_option : &Self::ChannelOption) -> Duration {
-    Duration::new(size_of_val(item) as u64,0).div(self.bandwidth as u32) + 
+  fn transmission_time<S : Sendable>(&self, item : &S ,ch_option : &Self::ChannelOption) -> Duration {
+    let link = self.links[Self::link_index(ch_option)];
+    Duration::new(item.message_size() as u64,0).div(link.bandwidth as u32) + 
      match  ch_option {
-      TaurusOption::ROW | TaurusOption::COL =>  self.startup.mul(self.broadcast_size as u32),
+      TaurusOption::ROW | TaurusOption::COL | TaurusOption::FIBRE =>
+        self.startup.mul(self.group_sizes[Self::link_index(ch_option)] as u32),
       _ => Duration::ZERO
     }
   }
 
-  fn latency(&self) -> Duration {
-    self.latency
+  fn latency(&self, ch_option : &Self::ChannelOption) -> Duration {
+    self.links[Self::link_index(ch_option)].latency
+  }
+
+  fn speed(&self) -> f64 {
+    self.speed
   }
 
   fn blank() -> Self {
-    TimedTaurusCore { latency: Duration::ZERO, bandwidth: 1, startup: Duration::ZERO, broadcast_size : 1, core : TaurusCore::new(0,0)}
+    TimedTaurusCore::new(Duration::ZERO, 1, Duration::ZERO, 1, TaurusCore::new(0,0))
   }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TaurusOption {
   LEFT,
   RIGHT,
@@ -85,45 +172,72 @@ pub enum TaurusOption {
   DOWN,
   ROW,
   COL,
+  /// Same position on the layer above, in a stacked network
+  ABOVE,
+  /// Same position on the layer below, in a stacked network
+  BELOW,
+  /// Broadcast to the same position on every layer, in a stacked network
+  FIBRE,
 }
 
 pub struct TaurusCore<T : Sendable> {
   pub row : usize,
   pub col : usize,
   core_comm : TaurusComm<T>,
+  watchdog : Option<Watchdog<TaurusOption>>,
+  memory : Option<MemoryLedger>,
+  torus : Torus,
 } 
 
 impl<T:Sendable> TaurusCore<T> {
   pub fn new(row : usize, col : usize) -> Self {
     TaurusCore{ row, col, 
                 core_comm : TaurusComm::new(), 
+                watchdog : None,
+                memory : None,
+                // Smallest torus holding the core until it is wired into a network
+                torus : Torus::new(row + 1, col + 1),
     }
   }
+
+  fn channel(&self, ch_option : &TaurusOption) -> &dyn Channel<T> {
+    match ch_option {
+      TaurusOption::LEFT => &self.core_comm.left,
+      TaurusOption::RIGHT => &self.core_comm.right,
+      TaurusOption::UP => &self.core_comm.up,
+      TaurusOption::DOWN => &self.core_comm.down,
+      TaurusOption::ROW => &self.core_comm.row,
+      TaurusOption::COL => &self.core_comm.col,
+      TaurusOption::ABOVE => &self.core_comm.above,
+      TaurusOption::BELOW => &self.core_comm.below,
+      TaurusOption::FIBRE => &self.core_comm.fibre,
+    }
+  }
+
 }
 
 impl<T:Sendable> Core<T> for TaurusCore<T> {
   type ChannelOption = TaurusOption;
 
   fn send(&mut self, data : T, ch_option : &Self::ChannelOption){
-    match ch_option {
-      TaurusOption::LEFT => self.core_comm.left.send(data),
-      TaurusOption::RIGHT => self.core_comm.right.send(data),
-      TaurusOption::UP => self.core_comm.up.send(data),
-      TaurusOption::DOWN => self.core_comm.down.send(data),
-      TaurusOption::ROW => self.core_comm.row.send(data),
-      TaurusOption::COL => self.core_comm.col.send(data),
+    if let Some(memory) = &self.memory {
+      // The message is buffered at every receiver until it is received
+      let bytes = data.payload_size();
+      for (row, col) in self.torus.destinations(self.row, self.col, ch_option) {
+        charge(memory, row, col, bytes);
+      }
+    }
+    self.channel(ch_option).send(data);
+    if let Some(watchdog) = &self.watchdog {
+      watchdog.sent();
     }
   }
 
   fn recv(&mut self, ch_option : &Self::ChannelOption) -> T{
-    let data = match ch_option {
-      TaurusOption::LEFT => self.core_comm.left.recv(),
-      TaurusOption::RIGHT => self.core_comm.right.recv(),
-      TaurusOption::UP => self.core_comm.up.recv(),
-      TaurusOption::DOWN => self.core_comm.down.recv(),
-      TaurusOption::ROW => self.core_comm.row.recv(),
-      TaurusOption::COL => self.core_comm.col.recv(),
-    };
+    let data = self.wait_for(ch_option);
+    if let Some(memory) = &self.memory {
+      memory.free(self.row, self.col, data.payload_size());
+    }
     data
   }
 
@@ -134,6 +248,80 @@ impl<T:Sendable> Core<T> for TaurusCore<T> {
   fn col(&self) -> usize {
     self.col
   }
+
+  fn start(&mut self) {
+    if let Some(watchdog) = &self.watchdog {
+      watchdog.start(self.row, self.col);
+    }
+  }
+
+  fn stop(&mut self) {
+    if let Some(watchdog) = &self.watchdog {
+      watchdog.finish(self.row, self.col);
+    }
+  }
+
+  fn allocate(&mut self, bytes : usize) {
+    if let Some(memory) = &self.memory {
+      charge(memory, self.row, self.col, bytes);
+    }
+  }
+
+  fn free(&mut self, bytes : usize) {
+    if let Some(memory) = &self.memory {
+      memory.free(self.row, self.col, bytes);
+    }
+  }
+}
+
+impl<T:Sendable> TaurusCore<T> {
+  /// Receives on `ch_option`, reporting to the watchdog while blocked
+  fn wait_for(&self, ch_option : &TaurusOption) -> T {
+    let watchdog = match &self.watchdog {
+      None => return self.channel(ch_option).recv(),
+      Some(watchdog) => watchdog,
+    };
+
+    loop {
+      let seen = watchdog.generation();
+      match self.channel(ch_option).recv_timeout(watchdog.poll()) {
+        Ok(data) => {
+          watchdog.unblocked(self.row, self.col);
+          return data;
+        },
+        Err(RecvTimeoutError::Timeout) => (),
+        // The sender most likely panicked, perhaps on a deadlock already
+        // reported
+        Err(RecvTimeoutError::Disconnected) => match watchdog.report() {
+          Some(report) => panic!("{}", report),
+          None => panic!("Core {} {} waiting on {:?}, whose senders have all gone", self.row, self.col, ch_option),
+        },
+      }
+      if let Err(report) = watchdog.blocked(self.row, self.col, ch_option, seen) {
+        panic!("{}", report);
+      }
+    }
+  }
+}
+
+impl<T:Sendable> Watchable<TaurusOption> for TaurusCore<T> {
+  fn watch(&mut self, watchdog : Watchdog<TaurusOption>) {
+    self.watchdog = Some(watchdog);
+  }
+}
+
+impl<T:Sendable> MemoryLimited for TaurusCore<T> {
+  fn limit_memory(&mut self, ledger : MemoryLedger) {
+    self.memory = Some(ledger);
+  }
+}
+
+impl<T:Sendable> Drop for TaurusCore<T> {
+  fn drop(&mut self) {
+    if let Some(watchdog) = &self.watchdog {
+      watchdog.finish(self.row, self.col);
+    }
+  }
 }
 
 #[derive(Clone,Copy)]
@@ -143,57 +331,109 @@ impl<T:Sendable> NetworkBuilder<T> for TaurusNetworkBuilder {
   type CoreType = TaurusCore<T>;
 
   fn build(&self, rows: usize, cols : usize) -> Vec<Self::CoreType> {
-      let num_cores = cols * rows;
-      let mut cores : Vec<TaurusCore<T>> = Vec::with_capacity(num_cores);
-      for row in 0..rows {
-        for col in 0..cols {
-          cores.push(TaurusCore::new(row, col))
-        }
-      }
+    build_stack(Torus::new(rows, cols))
+  }
+}
+
+/// Builds `layers` tori stacked on top of each other, see `Torus`. The
+/// processor needs `layers` times as many rows as each layer.
+#[derive(Clone,Copy)]
+pub struct StackedTaurusNetworkBuilder {
+  layers : usize,
+}
+
+impl StackedTaurusNetworkBuilder {
+  pub fn new(layers : usize) -> Self {
+    StackedTaurusNetworkBuilder { layers }
+  }
+}
+
+impl<T:Sendable> NetworkBuilder<T> for StackedTaurusNetworkBuilder {
+  type CoreType = TaurusCore<T>;
+
+  fn build(&self, rows: usize, cols : usize) -> Vec<Self::CoreType> {
+    build_stack(Torus::stacked(rows, cols, self.layers))
+  }
+
+  fn layers(&self) -> usize {
+    self.layers
+  }
+}
+
+fn build_stack<T:Sendable>(torus : Torus) -> Vec<TaurusCore<T>> {
+  let Torus { rows, cols, layers } = torus;
+  let layer_rows = torus.layer_rows();
+  let mut cores : Vec<TaurusCore<T>> = Vec::with_capacity(rows * cols);
+  for row in 0..rows {
+    for col in 0..cols {
+      let mut core = TaurusCore::new(row, col);
+      core.torus = torus;
+      cores.push(core)
+    }
+  }
+  let index = |(row, col) : (usize, usize)| row * cols + col;
 
-    for i in 0..rows {
-      let mut bchannels : Vec<Broadcast<T>> = Broadcast::new(cols);
-      for step in 0..cols {
-        let core_index = rows * i + step;
-        cores[core_index].core_comm.row = bchannels.pop().unwrap();
+  for row in 0..rows {
+    let mut bchannels : Vec<Broadcast<T>> = Broadcast::new(cols);
+    for col in 0..cols {
+      cores[index((row, col))].core_comm.row = bchannels.pop().unwrap();
+    }
+  }
+
+  for layer in 0..layers {
+    for col in 0..cols {
+      let mut bchannels : Vec<Broadcast<T>> = Broadcast::new(layer_rows);
+      for row in 0..layer_rows {
+        cores[index((layer * layer_rows + row, col))].core_comm.col = bchannels.pop().unwrap();
       }
     }
+  }
 
-    for i in 0..cols {
-      let mut bchannels : Vec<Broadcast<T>> = Broadcast::new(rows);
-      for step in 0..rows {
-        let core_index = rows * step + i;
-        cores[core_index].core_comm.col = bchannels.pop().unwrap();
+  for row in 0..layer_rows {
+    for col in 0..cols {
+      let mut bchannels : Vec<Broadcast<T>> = Broadcast::new(layers);
+      for layer in 0..layers {
+        cores[index((layer * layer_rows + row, col))].core_comm.fibre = bchannels.pop().unwrap();
       }
     }
-    
-    for i in 0..num_cores {
-      let (up, down) = Direct::new();
-      let up_index = i;
-      let down_index = ( num_cores + i - cols ) % num_cores;
+  }
 
-      cores[up_index].core_comm.up = up;
-      cores[down_index].core_comm.down = down; 
+  for row in 0..rows {
+    for col in 0..cols {
+      let i = index((row, col));
+
+      let (up, down) = Direct::new();
+      cores[i].core_comm.up = up;
+      cores[index(torus.neighbour(row, col, &TaurusOption::UP))].core_comm.down = down;
 
       let (right, left) = Direct::new();
-      let right_index = i;
-      let left_index = i - ( i % cols ) + ( (i +  1) % cols );
+      cores[i].core_comm.right = right;
+      cores[index(torus.neighbour(row, col, &TaurusOption::RIGHT))].core_comm.left = left;
 
-      cores[right_index].core_comm.right = right;
-      cores[left_index].core_comm.left = left; 
+      let (above, below) = Direct::new();
+      cores[i].core_comm.above = above;
+      cores[index(torus.neighbour(row, col, &TaurusOption::ABOVE))].core_comm.below = below;
     }
-    
-    cores.reverse();
-    return cores
   }
+  cores
 }
 
-#[derive(Clone,Copy)]
+/// How the compute speed of each core is chosen when building a network
+#[derive(Clone)]
+pub enum CoreSpeed {
+  Uniform,
+  Function(fn(usize, usize) -> f64),
+  Matrix(Matrix<f64>),
+}
+
+#[derive(Clone)]
 pub struct TimeTaurusNetworkBuilder {
   latency : Duration,
   bandwidth : usize,
   startup : Duration,
-  networkbuilder : TaurusNetworkBuilder
+  speed : CoreSpeed,
+  links : LinkModel,
+  networkbuilder : StackedTaurusNetworkBuilder
 }
 
 impl TimeTaurusNetworkBuilder{
@@ -203,8 +443,53 @@ impl TimeTaurusNetworkBuilder{
         latency : if latency != 0 {Duration::new(0,latency as u32)} else {Duration::ZERO},
         bandwidth : if bandwidth == 0 {1} else {bandwidth},
         startup : if startup != 0 {Duration::new(0,startup as u32)} else {Duration::ZERO},
-        networkbuilder : TaurusNetworkBuilder
+        speed : CoreSpeed::Uniform,
+        links : LinkModel::Uniform,
+        networkbuilder : StackedTaurusNetworkBuilder::new(1)
     }}
+
+  /// Stacks `layers` tori on top of each other, see `Torus`
+  pub fn with_layers(mut self, layers : usize) -> Self {
+    self.networkbuilder = StackedTaurusNetworkBuilder::new(layers);
+    self
+  }
+
+  /// Gives LEFT/RIGHT and UP/DOWN links separate parameters
+  pub fn with_directional_links(mut self, horizontal : Link, vertical : Link) -> Self {
+    self.links = LinkModel::Directional { horizontal, vertical };
+    self
+  }
+
+  /// Gives links which wrap around the edge of the torus separate parameters
+  pub fn with_wrap_around_links(mut self, local : Link, wrap : Link) -> Self {
+    self.links = LinkModel::WrapAround { local, wrap };
+    self
+  }
+
+  /// Splits the grid into `tile_rows` x `tile_cols` tiles (chips) with cheap
+  /// `intra` links inside a tile and expensive `inter` links between tiles
+  pub fn with_tiles(mut self, tile_rows : usize, tile_cols : usize, intra : Link, inter : Link) -> Self {
+    self.links = LinkModel::tiled(tile_rows, tile_cols, intra, inter);
+    self
+  }
+
+  /// Sets the parameters of individual links, see `LinkTable`
+  pub fn with_link_table(mut self, table : LinkTable) -> Self {
+    self.links = LinkModel::Table(table);
+    self
+  }
+
+  /// Sets the speed of the core at (row, col) to `f(row, col)`
+  pub fn with_speed_fn(mut self, f : fn(usize, usize) -> f64) -> Self {
+    self.speed = CoreSpeed::Function(f);
+    self
+  }
+
+  /// Sets the speed of the core at (row, col) to `speeds[row][col]`
+  pub fn with_speed_matrix(mut self, speeds : Matrix<f64>) -> Self {
+    self.speed = CoreSpeed::Matrix(speeds);
+    self
+  }
 }
 
 impl<T:Sendable> NetworkBuilder<T> for TimeTaurusNetworkBuilder {
@@ -212,9 +497,39 @@ impl<T:Sendable> NetworkBuilder<T> for TimeTaurusNetworkBuilder {
 
   fn build(&self, rows: usize, cols : usize) -> Vec<Self::CoreType> {
     let cores = self.networkbuilder.build(rows, cols); 
+    let speeds = <Self as NetworkBuilder<T>>::core_speeds(self, rows, cols);
+    let default = Link { latency : self.latency, bandwidth : self.bandwidth };
+    let torus = Torus::stacked(rows, cols, self.networkbuilder.layers);
+    let directions = [TaurusOption::LEFT, TaurusOption::RIGHT, TaurusOption::UP,
+                      TaurusOption::DOWN, TaurusOption::ROW, TaurusOption::COL,
+                      TaurusOption::ABOVE, TaurusOption::BELOW, TaurusOption::FIBRE];
     cores.into_iter()
-      .map(|core| TimedTaurusCore::new(self.latency, self.bandwidth, self.startup, rows, core))
+      .map(|core| {
+        let (row, col) = (core.row, core.col);
+        let speed = speeds.speed(row, col);
+        directions.iter().fold(
+          TimedTaurusCore::new(self.latency, self.bandwidth, self.startup, rows, core).with_speed(speed),
+          |timed, direction| timed.with_link(direction, self.links.link(row, col, &torus, direction, default))
+            .with_group_size(direction, torus.group_size(direction)))
+      })
       .collect()
   }
+
+  fn core_speeds(&self, rows: usize, cols : usize) -> CoreSpeeds {
+    match &self.speed {
+      CoreSpeed::Uniform => CoreSpeeds::uniform(rows, cols),
+      CoreSpeed::Function(f) => CoreSpeeds::from_fn(rows, cols, f),
+      CoreSpeed::Matrix(speeds) => {
+        let speeds = CoreSpeeds::from_matrix(speeds);
+        assert_eq!((speeds.rows, speeds.cols), (rows, cols),
+                   "the speed matrix does not match the {rows} x {cols} grid");
+        speeds
+      },
+    }
+  }
+
+  fn layers(&self) -> usize {
+    self.networkbuilder.layers
+  }
 }
 
@@ -1,5 +1,9 @@
 use super::*;
 use super::taurus::*;
+use super::taurus::link::*;
+use super::watchdog::WatchdogNetworkBuilder;
+use super::memory::MemoryLimitNetworkBuilder;
+use super::distribution::{Distribution, Redistribution, Skew};
 
 
 #[test]
@@ -66,6 +70,226 @@ fn general_correct_broadcast(){
   assert_eq!(processor.cores[1].recv(&TaurusOption::COL), 3);
   assert_eq!(processor.cores[3].recv(&TaurusOption::COL), 3);
 }
+#[test]
+fn run_core_at_uses_requested_core(){
+  let network_builder = TaurusNetworkBuilder;
+  let mut processor : Processor <(usize,usize),i32, TaurusCore<i32>> = 
+    Processor::new(2,3, network_builder);
+
+  processor.run_core_at(1, 2, |core_info : &mut TaurusCore<i32>| (core_info.row(), core_info.col()));
+  processor.run_core_at(0, 1, |core_info : &mut TaurusCore<i32>| (core_info.row(), core_info.col()));
+  assert_eq!(processor.cores.len(), 4);
+  let grid = processor.collect_grid();
+
+  assert_eq!(grid.get(1, 2), Some(&(1, 2)));
+  assert_eq!(grid.get(0, 1), Some(&(0, 1)));
+  assert_eq!(grid.get(0, 0), None);
+  // Finished cores are handed back so the processor can be reused
+  assert_eq!(processor.cores.len(), 6);
+}
+
+#[test]
+fn run_core_hands_out_cores_in_row_major_order(){
+  let mut processor : Processor <(usize,usize),i32, TaurusCore<i32>> = 
+    Processor::new(2,3, TaurusNetworkBuilder);
+
+  for _ in 0..3 {
+    processor.run_core(|core_info : &mut TaurusCore<i32>| (core_info.row(), core_info.col()));
+  }
+  let grid = processor.collect_grid();
+
+  assert_eq!(grid.iter().map(|(_, _, &core)| core).collect::<Vec<_>>(), vec![(0, 0), (0, 1), (0, 2)]);
+}
+
+#[test]
+fn run_all_returns_grid_indexed_results(){
+  let network_builder = TaurusNetworkBuilder;
+  let mut processor : Processor <((usize,usize),(usize,usize)),i32, TaurusCore<i32>> = 
+    Processor::new(3,3, network_builder);
+
+  processor.run_all(|row, col| move |core_info : &mut TaurusCore<i32>| {
+    ((row, col), (core_info.row(), core_info.col()))
+  });
+  let grid = processor.collect_grid();
+
+  assert_eq!(grid.iter().count(), 9);
+  for (row, col, &(requested, core)) in grid.iter() {
+    assert_eq!((row, col), requested);
+    assert_eq!((row, col), core);
+  }
+}
+
+#[test]
+fn link_model_wrap_around(){
+  let local = Link::new(10, 4);
+  let wrap = Link::new(100, 1);
+  let model = LinkModel::WrapAround { local, wrap };
+  let default = Link::new(0, 1);
+
+  assert_eq!(model.link(0, 0, &Torus::new(3, 3), &TaurusOption::LEFT, default), wrap);
+  assert_eq!(model.link(0, 1, &Torus::new(3, 3), &TaurusOption::LEFT, default), local);
+  assert_eq!(model.link(2, 1, &Torus::new(3, 3), &TaurusOption::DOWN, default), wrap);
+  assert_eq!(model.link(1, 2, &Torus::new(3, 3), &TaurusOption::UP, default), local);
+  assert_eq!(model.link(0, 0, &Torus::new(3, 3), &TaurusOption::ROW, default), default);
+}
+
+#[test]
+fn link_model_tiled(){
+  let intra = Link::new(10, 4);
+  let inter = Link::new(100, 1);
+  let model = LinkModel::tiled(2, 2, intra, inter);
+  let default = Link::new(0, 1);
+
+  assert_eq!(model.link(0, 0, &Torus::new(4, 4), &TaurusOption::RIGHT, default), intra);
+  assert_eq!(model.link(0, 1, &Torus::new(4, 4), &TaurusOption::RIGHT, default), inter);
+  assert_eq!(model.link(0, 0, &Torus::new(4, 4), &TaurusOption::LEFT, default), inter);
+  assert_eq!(model.link(1, 3, &Torus::new(4, 4), &TaurusOption::UP, default), intra);
+  assert_eq!(model.link(1, 3, &Torus::new(4, 4), &TaurusOption::DOWN, default), inter);
+  assert_eq!(model.link(1, 3, &Torus::new(4, 4), &TaurusOption::ROW, default), inter);
+  assert_eq!(model.link(1, 3, &Torus::new(2, 2), &TaurusOption::COL, default), intra);
+}
+
+#[test]
+#[should_panic(expected = "tiles need cores, not 0 x 2")]
+fn link_model_tiled_rejects_empty_tiles(){
+  LinkModel::tiled(0, 2, Link::new(10, 4), Link::new(100, 1));
+}
+
+#[test]
+#[should_panic(expected = "tiles need cores, not 2 x 0")]
+fn with_tiles_rejects_empty_tiles(){
+  TimeTaurusNetworkBuilder::new(0, 1, 0)
+    .with_tiles(2, 0, Link::new(10, 4), Link::new(100, 1));
+}
+
+#[test]
+fn stacked_torus_neighbours(){
+  // Two layers of 2 x 3 cores
+  let torus = Torus::stacked(4, 3, 2);
+
+  assert_eq!(torus.neighbour(2, 1, &TaurusOption::UP), (3, 1));
+  assert_eq!(torus.neighbour(3, 1, &TaurusOption::DOWN), (2, 1));
+  assert_eq!(torus.neighbour(1, 2, &TaurusOption::ABOVE), (3, 2));
+  assert_eq!(torus.neighbour(1, 2, &TaurusOption::BELOW), (3, 2));
+  assert_eq!(torus.destinations(3, 0, &TaurusOption::COL), vec![(2, 0), (3, 0)]);
+  assert_eq!(torus.destinations(3, 0, &TaurusOption::FIBRE), vec![(1, 0), (3, 0)]);
+  assert!(torus.is_wrap_around(2, 0, &TaurusOption::UP));
+  assert!(!torus.is_wrap_around(1, 0, &TaurusOption::BELOW));
+  assert_eq!(torus.group_size(&TaurusOption::ROW), 3);
+  assert_eq!(torus.group_size(&TaurusOption::COL), 2);
+  assert_eq!(torus.group_size(&TaurusOption::FIBRE), 2);
+  assert_eq!(torus.group_size(&TaurusOption::LEFT), 1);
+}
+
+#[test]
+fn stacked_broadcasts_pay_a_startup_per_core_reached(){
+  use std::time::Duration;
+  // Two layers of 4 x 2 cores with a startup of 10ns
+  let cores = NetworkBuilder::<()>::build(&TimeTaurusNetworkBuilder::new(0, 1, 10).with_layers(2), 8, 2);
+  let startups = |direction : TaurusOption| cores[0].transmission_time(&(), &direction);
+
+  assert_eq!(startups(TaurusOption::ROW), Duration::from_nanos(20));
+  assert_eq!(startups(TaurusOption::COL), Duration::from_nanos(40));
+  assert_eq!(startups(TaurusOption::FIBRE), Duration::from_nanos(20));
+  assert_eq!(startups(TaurusOption::RIGHT), Duration::ZERO);
+}
+
+#[test]
+fn stacked_network_channels(){
+  type Received = (usize, usize, usize);
+  let mut processor : Processor <Received, (usize, usize), TaurusCore<(usize, usize)>> = 
+    Processor::new(4, 2, StackedTaurusNetworkBuilder::new(2));
+  processor.run_all(|_, _| |core_info : &mut TaurusCore<(usize, usize)>| {
+    let position = (core_info.row(), core_info.col());
+    // Column broadcasts stay inside a layer
+    if core_info.row() % 2 == 0 {
+      core_info.send(position, &TaurusOption::COL);
+    }
+    let column = core_info.recv(&TaurusOption::COL);
+    core_info.send(position, &TaurusOption::ABOVE);
+    let below = core_info.recv(&TaurusOption::BELOW);
+    if core_info.row() < 2 {
+      core_info.send(position, &TaurusOption::FIBRE);
+    }
+    let fibre = core_info.recv(&TaurusOption::FIBRE);
+    (column.0, below.0, fibre.0)
+  });
+  let grid = processor.collect_grid();
+
+  for (row, _, &(column, below, fibre)) in grid.iter() {
+    assert_eq!(column, row - row % 2);
+    assert_eq!(below, (row + 2) % 4);
+    assert_eq!(fibre, row % 2);
+  }
+  assert_eq!(processor.torus(), Torus::stacked(4, 2, 2));
+}
+
+#[test]
+fn link_table_from_json(){
+  let table : LinkTable = serde_json::from_str(
+    r#"{"links": [{"row": 1, "col": 0, "direction": "UP", "latency": 50, "bandwidth": 2}]}"#
+  ).unwrap();
+  let model = LinkModel::Table(table);
+  let default = Link::new(0, 1);
+
+  assert_eq!(model.link(1, 0, &Torus::new(2, 2), &TaurusOption::UP, default), Link::new(50, 2));
+  assert_eq!(model.link(1, 0, &Torus::new(2, 2), &TaurusOption::DOWN, default), default);
+}
+
+#[test]
+#[should_panic(expected = "Core 1 0 waiting on LEFT")]
+fn watchdog_reports_deadlock(){
+  let network_builder = WatchdogNetworkBuilder::new(TaurusNetworkBuilder)
+    .with_poll(Duration::from_millis(10));
+  let mut processor : Processor <i32,i32, TaurusCore<i32>> = 
+    Processor::new(2,2, network_builder);
+
+  processor.run_all(|_, _| |core_info : &mut TaurusCore<i32>| core_info.recv(&TaurusOption::LEFT));
+  processor.collect_results();
+}
+
+#[test]
+fn watchdog_waits_for_cores_launched_late(){
+  let network_builder = WatchdogNetworkBuilder::new(TaurusNetworkBuilder)
+    .with_poll(Duration::from_millis(1));
+  let mut processor : Processor <i32,i32, TaurusCore<i32>> = 
+    Processor::new(1,2, network_builder);
+
+  // Core 0 0 blocks for many polls before its only sender starts
+  processor.run_core_at(0, 0, |core_info : &mut TaurusCore<i32>| core_info.recv(&TaurusOption::RIGHT));
+  std::thread::sleep(Duration::from_millis(50));
+  processor.run_core_at(0, 1, |core_info : &mut TaurusCore<i32>| {
+    core_info.send(7, &TaurusOption::LEFT);
+    0
+  });
+  let grid = processor.collect_grid();
+
+  assert_eq!(grid.get(0, 0), Some(&7));
+}
+
+#[test]
+fn watchdog_allows_progress(){
+  let network_builder = WatchdogNetworkBuilder::new(TaurusNetworkBuilder)
+    .with_poll(Duration::from_millis(1));
+  let mut processor : Processor <i32,i32, TaurusCore<i32>> = 
+    Processor::new(2,2, network_builder);
+
+  processor.run_all(|row, col| move |core_info : &mut TaurusCore<i32>| {
+    let mut value = (row * 2 + col) as i32;
+    for _ in 0..50 {
+      core_info.send(value, &TaurusOption::LEFT);
+      value = core_info.recv(&TaurusOption::RIGHT);
+      core_info.send(value, &TaurusOption::UP);
+      value = core_info.recv(&TaurusOption::DOWN);
+    }
+    value
+  });
+  let grid = processor.collect_grid();
+
+  assert_eq!(grid.get(0, 0), Some(&0));
+  assert_eq!(grid.get(1, 1), Some(&3));
+}
+
 // ------------------------------------------------------------
 
 #[test]
@@ -94,6 +318,74 @@ fn get_submatrices_dim_along_axis_less_processors_divisible(){
 
 // ------------------------------------------------------------
 
+#[test]
+fn get_weighted_dim_along_axis_uniform_matches_unweighted() {
+  for (processors, length) in [(6, 4), (4, 4), (6, 17), (6, 18)] {
+    assert_eq!(get_weighted_dim_along_axis(&vec![1.0; processors], length),
+               get_submatrices_dim_along_axis(processors, length));
+  }
+}
+
+#[test]
+fn get_weighted_dim_along_axis_faster_gets_more() {
+  let submatrices_dims = get_weighted_dim_along_axis(&[1.0, 3.0], 8);
+  assert_eq!(submatrices_dims, vec![2,6]);
+}
+
+#[test]
+fn get_weighted_dim_along_axis_remainder_to_largest_fraction() {
+  let submatrices_dims = get_weighted_dim_along_axis(&[1.0, 1.0, 2.0], 7);
+  assert_eq!(submatrices_dims, vec![2,2,3]);
+}
+
+#[test]
+fn get_balanced_submatrices_dim_square(){
+  let speeds = CoreSpeeds::from_matrix(&vec![
+    vec![1.0, 1.0],
+    vec![1.0, 5.0],
+  ]);
+  let submatrices_dims = get_balanced_submatrices_dim(&speeds, 10, 10);
+  assert_eq!(submatrices_dims[0],
+    SubmatrixDim {
+      start_row : 0,
+      start_col : 0,
+      width : 3,
+      height : 3,
+  });
+  assert_eq!(submatrices_dims[3],
+    SubmatrixDim {
+      start_row : 3,
+      start_col : 3,
+      width : 7,
+      height : 7,
+  });
+}
+
+#[test]
+#[should_panic(expected = "core 1 0 has speed 0, which is not positive")]
+fn core_speeds_reject_zero(){
+  CoreSpeeds::from_matrix(&vec![vec![1.0, 1.0], vec![0.0, 1.0]]);
+}
+
+#[test]
+#[should_panic(expected = "core 0 1 has speed NaN, which is not positive")]
+fn core_speeds_reject_nan(){
+  CoreSpeeds::from_fn(1, 2, |_, col| if col == 1 { f64::NAN } else { 1.0 });
+}
+
+#[test]
+#[should_panic(expected = "the speed matrix is empty")]
+fn core_speeds_reject_empty(){
+  CoreSpeeds::from_matrix(&Vec::new());
+}
+
+#[test]
+#[should_panic(expected = "the speed matrix does not match the 2 x 3 grid")]
+fn core_speeds_must_match_grid(){
+  let builder = TimeTaurusNetworkBuilder::new(0, 1, 0).with_speed_matrix(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+  NetworkBuilder::<usize>::core_speeds(&builder, 2, 3);
+}
+
 #[test]
 fn get_submatrices_dim_square_equal(){
   let submatrices_dims = get_submatrices_dim(2,2,2,2);
@@ -329,3 +621,187 @@ fn get_submatrices_square_diff(){
 
 // ------------------------------------------------------------
 
+
+// ------------------------------------------------------------
+
+#[test]
+fn block_cyclic_indices(){
+  let distribution = Distribution::block_cyclic(2, 3, 2, 1);
+  let (rows, cols) = distribution.indices(1, 2, 7, 7);
+  assert_eq!(rows, vec![2,3,6]);
+  assert_eq!(cols, vec![2,5]);
+}
+
+#[test]
+fn block_cyclic_partitions_any_matrix(){
+  // Non-square grids, blocks which are not square and matrices which are not
+  // a multiple of the blocks
+  for (rows, cols, block_rows, block_cols) in [(2, 3, 2, 1), (3, 2, 1, 3), (1, 4, 3, 2)] {
+    let distribution = Distribution::block_cyclic(rows, cols, block_rows, block_cols);
+    let m : Vec<Vec<usize>> = (0..7).map(|i| (0..5).map(|j| i * 5 + j).collect()).collect();
+    let mut gathered = vec![vec![0; 5]; 7];
+    let mut held = 0;
+    for (index, local) in distribution.scatter(&m).iter().enumerate() {
+      held += local.iter().map(Vec::len).sum::<usize>();
+      distribution.gather(index / cols, index % cols, local, &mut gathered);
+    }
+    assert_eq!(held, 35);
+    assert_eq!(gathered, m);
+  }
+
+  let (rows, cols) = Distribution::block_cyclic(3, 2, 1, 3).indices(2, 1, 7, 5);
+  assert_eq!(rows, vec![2, 5]);
+  assert_eq!(cols, vec![3, 4]);
+}
+
+#[test]
+#[should_panic(expected = "needs blocks, not 0 x 2")]
+fn block_cyclic_rejects_empty_blocks(){
+  Distribution::block_cyclic(2, 2, 0, 2);
+}
+
+#[test]
+fn block_distribution_matches_get_submatrices(){
+  let m : Vec<Vec<usize>> = (0..5).map(|i| (0..5).map(|j| i * 5 + j).collect()).collect();
+  let distribution = Distribution::block(2, 2);
+  assert_eq!(distribution.scatter(&m), get_submatrices(2, 2, &m));
+}
+
+#[test]
+fn block_cyclic_scatter_gather(){
+  let m : Vec<Vec<usize>> = (0..7).map(|i| (0..5).map(|j| i * 5 + j).collect()).collect();
+  let distribution = Distribution::block_cyclic(2, 2, 2, 2);
+  let locals = distribution.scatter(&m);
+  assert_eq!(locals[0], vec![
+    vec![0,1,4],
+    vec![5,6,9],
+    vec![20,21,24],
+    vec![25,26,29],
+  ]);
+
+  let mut gathered = vec![vec![0; 5]; 7];
+  for (index, local) in locals.iter().enumerate() {
+    distribution.gather(index / 2, index % 2, local, &mut gathered);
+  }
+  assert_eq!(gathered, m);
+}
+
+#[test]
+fn skewed_indices(){
+  let distribution = Distribution::block(3, 3);
+  let left = Distribution::skewed(distribution.clone(), Skew::Left);
+  let up = Distribution::skewed(distribution.clone(), Skew::Up);
+  assert_eq!(left.indices(1, 2, 3, 3), distribution.indices(1, 0, 3, 3));
+  assert_eq!(up.indices(1, 2, 3, 3), distribution.indices(0, 2, 3, 3));
+}
+
+#[test]
+fn quadrant_indices(){
+  // Core (1, 0) holds rows 2..3 and columns 0..2 of every 3 x 4 block
+  let distribution = Distribution::quadrants(Distribution::block(2, 2), 1);
+  let (rows, cols) = distribution.indices(1, 0, 6, 8);
+  assert_eq!(rows, vec![2, 5]);
+  assert_eq!(cols, vec![0, 1, 4, 5]);
+
+  let m : Vec<Vec<usize>> = (0..8).map(|i| (0..8).map(|j| i * 8 + j).collect()).collect();
+  let locals = Distribution::quadrants(Distribution::block(2, 2), 2).scatter(&m);
+  assert_eq!(locals[3][1], vec![25, 27, 29, 31]);
+  assert_eq!(Distribution::quadrants(Distribution::block(2, 2), 0), Distribution::block(2, 2));
+}
+
+fn redistribute(from : Distribution, to : Distribution, m : &Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+  let redistribution = Redistribution::new(from.clone(), to.clone(), m.len(), m[0].len(), 2, 3);
+  type Block = Vec<Vec<usize>>;
+  let mut processor : Processor <Block, Block, TaurusCore<Block>> = 
+    Processor::new(2, 3, TaurusNetworkBuilder);
+  let mut blocks = from.scatter(m).into_iter();
+  processor.run_all(|row, col| {
+    let block = if row < from.rows() && col < from.cols() { blocks.next().unwrap() } else { Vec::new() };
+    let redistribution = redistribution.clone();
+    move |core_info : &mut TaurusCore<Block>| redistribution.run(block, core_info)
+  });
+  let grid = processor.collect_grid();
+
+  let mut result = vec![vec![0; m[0].len()]; m.len()];
+  for (row, col, block) in grid.iter() {
+    if row < to.rows() && col < to.cols() {
+      to.gather(row, col, block, &mut result);
+    } else {
+      assert!(block.is_empty());
+    }
+  }
+  result
+}
+
+#[test]
+fn redistribution_between_layouts(){
+  let m : Vec<Vec<usize>> = (0..7).map(|i| (0..8).map(|j| i * 8 + j).collect()).collect();
+  let block = Distribution::block(2, 3);
+  let block_cyclic = Distribution::block_cyclic(2, 3, 2, 1);
+  let narrow = Distribution::block(1, 2);
+  let skewed = Distribution::skewed(Distribution::block(2, 2), Skew::Left);
+
+  assert_eq!(redistribute(block.clone(), block_cyclic.clone(), &m), m);
+  assert_eq!(redistribute(block_cyclic, block.clone(), &m), m);
+  assert_eq!(redistribute(block, narrow.clone(), &m), m);
+  assert_eq!(redistribute(narrow, skewed.clone(), &m), m);
+  assert_eq!(redistribute(skewed, Distribution::block(2, 2), &m), m);
+}
+
+#[test]
+#[should_panic(expected = "cannot redistribute between layouts on 2 and 1 layers")]
+fn redistribution_rejects_stacked_layouts(){
+  let stacked = Distribution::block(4, 2).on_first_layer(2);
+  Redistribution::new(stacked, Distribution::block(4, 2), 8, 8, 4, 2);
+}
+
+#[test]
+#[should_panic(expected = "Core 0 1 out of memory: requested 128 bytes with 100 of 100 available")]
+fn memory_limit_rejects_buffered_message(){
+  let network_builder = MemoryLimitNetworkBuilder::new(TaurusNetworkBuilder, 100);
+  let mut processor : Processor <(), Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(1, 2, network_builder);
+
+  processor.run_core_at(0, 0, |core_info : &mut TaurusCore<Matrix<isize>>| {
+    core_info.send(vec![vec![0; 4]; 4], &TaurusOption::LEFT);
+  });
+  processor.collect_results();
+}
+
+#[test]
+fn memory_limit_frees_received_messages(){
+  let network_builder = MemoryLimitNetworkBuilder::new(TaurusNetworkBuilder, 128);
+  let mut processor : Processor <(), Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(1, 2, network_builder);
+
+  processor.run_core_at(0, 0, |core_info : &mut TaurusCore<Matrix<isize>>| {
+    for _ in 0..3 {
+      core_info.send(vec![vec![0; 4]; 4], &TaurusOption::LEFT);
+      core_info.recv(&TaurusOption::RIGHT);
+    }
+  });
+  processor.run_core_at(0, 1, |core_info : &mut TaurusCore<Matrix<isize>>| {
+    for _ in 0..3 {
+      let block = core_info.recv(&TaurusOption::RIGHT);
+      core_info.allocate(block.payload_size());
+      core_info.free(block.payload_size());
+      core_info.send(Vec::new(), &TaurusOption::LEFT);
+    }
+  });
+  processor.collect_results();
+}
+
+#[test]
+#[should_panic(expected = "out of memory: requested 64 bytes with 0 of 64 available")]
+fn memory_limit_counts_local_blocks(){
+  let network_builder = MemoryLimitNetworkBuilder::new(TaurusNetworkBuilder, 1024)
+    .with_capacity_fn(|row, _| if row == 0 { 64 } else { 1024 });
+  let mut processor : Processor <(), Matrix<isize>, TaurusCore<Matrix<isize>>> = 
+    Processor::new(1, 1, network_builder);
+
+  processor.run_core(|core_info : &mut TaurusCore<Matrix<isize>>| {
+    core_info.allocate(64);
+    core_info.allocate(64);
+  });
+  processor.collect_results();
+}
@@ -0,0 +1,179 @@
+use std::{fmt::Debug, sync::{Arc, Mutex}, time::Duration};
+
+use crate::broadcast::Sendable;
+
+use super::{Core, NetworkBuilder, CoreSpeeds};
+
+#[derive(Clone, Debug)]
+enum CoreState<O> {
+  Idle,
+  Running,
+  Blocked { on : O, seen : u64 },
+  Finished,
+}
+
+struct WatchState<O> {
+  cols : usize,
+  generation : u64,
+  cores : Vec<CoreState<O>>,
+  report : Option<String>,
+}
+
+/// Shared record of which cores are blocked in `recv` and on which channel.
+///
+/// Every send bumps a generation counter. A core which times out waiting for
+/// a message records the generation it read before it started waiting, so
+/// when every live core is blocked and none of them could have missed a send,
+/// the run can never make progress again. Cores count as live from when the
+/// watchdog is created until they finish, so a core launched late is waited
+/// for, but a run which never launches some cores is not diagnosed until
+/// they have run once.
+pub struct Watchdog<O> {
+  state : Arc<Mutex<WatchState<O>>>,
+  poll : Duration,
+}
+
+impl<O> Clone for Watchdog<O> {
+  fn clone(&self) -> Self {
+    Watchdog { state : Arc::clone(&self.state), poll : self.poll }
+  }
+}
+
+impl<O : Clone + Debug> Watchdog<O> {
+  /// Creates a watchdog for a `rows` x `cols` grid. Blocked cores check for a
+  /// deadlock every `poll`.
+  pub fn new(rows : usize, cols : usize, poll : Duration) -> Self {
+    Watchdog {
+      state : Arc::new(Mutex::new(WatchState {
+        cols,
+        generation : 0,
+        cores : vec![CoreState::Idle; rows * cols],
+        report : None,
+      })),
+      poll,
+    }
+  }
+
+  pub fn poll(&self) -> Duration {
+    self.poll
+  }
+
+  fn set(&self, row : usize, col : usize, core_state : CoreState<O>) {
+    let mut state = self.state.lock().unwrap();
+    let index = row * state.cols + col;
+    state.cores[index] = core_state;
+  }
+
+  pub fn start(&self, row : usize, col : usize) {
+    self.set(row, col, CoreState::Running);
+  }
+
+  pub fn finish(&self, row : usize, col : usize) {
+    self.set(row, col, CoreState::Finished);
+  }
+
+  pub fn unblocked(&self, row : usize, col : usize) {
+    self.set(row, col, CoreState::Running);
+  }
+
+  pub fn generation(&self) -> u64 {
+    self.state.lock().unwrap().generation
+  }
+
+  pub fn sent(&self) {
+    self.state.lock().unwrap().generation += 1;
+  }
+
+  /// Report of the deadlock, once one has been detected
+  pub fn report(&self) -> Option<String> {
+    self.state.lock().unwrap().report.clone()
+  }
+
+  /// Records that the core at (`row`, `col`) found no message on `on` after
+  /// reading generation `seen`.
+  ///
+  /// # Returns
+  /// Returns an error containing a report of every blocked core if the run is
+  /// deadlocked
+  pub fn blocked(&self, row : usize, col : usize, on : &O, seen : u64) -> Result<(), String> {
+    let mut state = self.state.lock().unwrap();
+    let index = row * state.cols + col;
+    state.cores[index] = CoreState::Blocked { on : on.clone(), seen };
+
+    if let Some(report) = &state.report {
+      return Err(report.clone());
+    }
+
+    let generation = state.generation;
+    // Cores which have not started yet may still send once they do
+    let deadlocked = state.cores.iter().all(|core_state| match core_state {
+      CoreState::Idle | CoreState::Running => false,
+      CoreState::Blocked { seen, .. } => *seen == generation,
+      CoreState::Finished => true,
+    });
+    if !deadlocked {
+      return Ok(());
+    }
+
+    let mut report = String::from("Deadlock detected: every running core is blocked in recv");
+    for (index, core_state) in state.cores.iter().enumerate() {
+      if let CoreState::Blocked { on, .. } = core_state {
+        report.push_str(&format!("\n  Core {} {} waiting on {:?}", index / state.cols, index % state.cols, on));
+      }
+    }
+    state.report = Some(report.clone());
+    Err(report)
+  }
+}
+
+/// Cores which can report their progress to a `Watchdog`
+pub trait Watchable<O> {
+  fn watch(&mut self, watchdog : Watchdog<O>);
+}
+
+/// Wraps another `NetworkBuilder` so that its cores share a `Watchdog`. A run
+/// in which every live core is blocked in `recv` panics with a report naming
+/// each core and the channel it is waiting on instead of hanging.
+#[derive(Clone)]
+pub struct WatchdogNetworkBuilder<B> {
+  networkbuilder : B,
+  poll : Duration,
+}
+
+impl<B> WatchdogNetworkBuilder<B> {
+  pub fn new(networkbuilder : B) -> Self {
+    WatchdogNetworkBuilder { networkbuilder, poll : Duration::from_millis(100) }
+  }
+
+  /// Sets how often blocked cores check for a deadlock
+  pub fn with_poll(mut self, poll : Duration) -> Self {
+    self.poll = poll;
+    self
+  }
+}
+
+impl<T, B> NetworkBuilder<T> for WatchdogNetworkBuilder<B>
+  where T : Sendable,
+        B : NetworkBuilder<T>,
+        B::CoreType : Watchable<<B::CoreType as Core<T>>::ChannelOption>,
+        <B::CoreType as Core<T>>::ChannelOption : Clone + Debug,
+{
+  type CoreType = B::CoreType;
+
+  fn build(&self, rows: usize, cols : usize) -> Vec<Self::CoreType> {
+    let watchdog = Watchdog::new(rows, cols, self.poll);
+    let mut cores = self.networkbuilder.build(rows, cols);
+    for core in cores.iter_mut() {
+      core.watch(watchdog.clone());
+    }
+    cores
+  }
+
+  fn core_speeds(&self, rows: usize, cols : usize) -> CoreSpeeds {
+    self.networkbuilder.core_speeds(rows, cols)
+  }
+
+  fn layers(&self) -> usize {
+    self.networkbuilder.layers()
+  }
+}
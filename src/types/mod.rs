@@ -1,11 +1,21 @@
 use std::fmt::{Debug,Display,Formatter,Result};
 use std::time::Duration;
 use crate::broadcast::Sendable;
-use crate::matmul::Multiplicable;
+use crate::matmul::{Semiring, Ring, sparse::block_sizes};
 use serde::{Serialize,Deserialize};
 
+pub mod semiring;
 
-#[derive(Clone,Debug,Serialize, Deserialize)]
+
+/// Length `w` of the shortest known path to a node together with the node
+/// `p` it is reached from, a min-plus `Semiring` which keeps the earlier of
+/// two equally short paths. Missing paths have an infinite length, which the
+/// public API reports as `-1.0` like the graph files.
+/// Elements on the diagonal stay at their node and name `Msg::STAY` as their
+/// predecessor, so that multiplying by them keeps the predecessor of the
+/// path so far, while every edge, including one of length 0, records the
+/// node it leaves from.
+#[derive(Clone,Debug,PartialEq,Serialize, Deserialize)]
 pub struct Msg {
   w : f64,
   p : usize,
@@ -18,27 +28,37 @@ impl Display for Msg {
 }
 
 impl Msg {
+  /// Predecessor of the elements which stay at their node, reported by
+  /// `unzip` as the node itself
+  pub const STAY : usize = usize::MAX;
+
+  /// A negative `w` marks a missing path
   pub fn new(w : f64, p : usize) -> Msg {
     return Msg {
-      w,
+      w : if w < 0.0 { f64::INFINITY } else { w },
       p,
     }
   }
 
+  /// Pairs up lengths and predecessors, marking the diagonal elements of
+  /// length 0 as staying at their node
   pub fn zip(matrix_w : &Matrix<f64>, matrix_p : &Matrix<usize>) -> Matrix<Msg> {
-  matrix_w.iter().zip(matrix_p.iter())
-    .map(|(w_row, p_row)| w_row.iter().zip(p_row.into_iter())
-      .map(|(&w, &p)| Msg { w, p }  ).collect::<Vec<Msg>>()
+  matrix_w.iter().zip(matrix_p.iter()).enumerate()
+    .map(|(i, (w_row, p_row))| w_row.iter().zip(p_row.into_iter()).enumerate()
+      .map(|(j, (&w, &p))| if i == j && w == 0.0 { Msg::one() } else { Msg::new(w, p) })
+      .collect::<Vec<Msg>>()
     ).collect::<Matrix<Msg>>()
   }
 
+  /// Splits lengths and predecessors, reporting `Msg::STAY` as the node
+  /// itself
   pub fn unzip(matrix_m : &Matrix<Msg>) -> (Matrix<f64>, Matrix<usize>) {
   matrix_m.iter().fold(
     (Vec::new(), Vec::new()), |(mut matrix_w, mut matrix_p), inner| {
-      let (w_inner, p_inner): (Vec<f64>, Vec<usize>) = inner.iter()
-        .fold((Vec::new(), Vec::new()), |(mut vec_w, mut vec_p), msg| {
+      let (w_inner, p_inner): (Vec<f64>, Vec<usize>) = inner.iter().enumerate()
+        .fold((Vec::new(), Vec::new()), |(mut vec_w, mut vec_p), (j, msg)| {
           vec_w.push(msg.get_w());
-          vec_p.push(msg.get_p());
+          vec_p.push(if msg.p == Msg::STAY { j } else { msg.p });
           (vec_w, vec_p)
         });
       matrix_w.push(w_inner);
@@ -47,84 +67,174 @@ impl Msg {
     })
   }
 
+  /// Length of the path, or `-1.0` if there is none
   pub fn get_w(&self) -> f64 {
-    return self.w;
+    if self.w.is_infinite() { -1.0 } else { self.w }
   }
 
+  /// Node the path arrives from, or `Msg::STAY` on the diagonal
   pub fn get_p(&self) -> usize {
     return self.p;
   }
 }
 
-impl Sendable for Msg {}
-impl Multiplicable for Msg {
-  fn initial_c (a : &Matrix<Self>, _ : &Matrix<Self>) -> Matrix<Self> {
-    a.clone()
+impl Sendable for Msg {
+  fn is_blank(&self) -> bool {
+    self.is_zero()
+  }
+}
+impl Semiring for Msg {
+  const IDEMPOTENT : bool = true;
+
+  fn zero() -> Self {
+    Msg { w : f64::INFINITY, p : 0 }
+  }
+  // Missing paths are zero whichever node they name
+  fn is_zero(&self) -> bool {
+    self.w.is_infinite()
   }
-  fn singleton_matrix(a : Self, b : Self, c : Self) -> Self {
-    let mut temp = c;
-    if a.w > 0.0 && b.w > 0.0  && ( temp.w < 0.0 || a.w + b.w < temp.w ){
-      temp.w = a.w + b.w;
-      temp.p = b.p;
+
+  fn one() -> Self {
+    Msg { w : 0.0, p : Msg::STAY }
+  }
+
+  fn add(a : Self, b : Self) -> Self {
+    if b.w < a.w { b } else { a }
+  }
+
+  fn mul(a : Self, b : Self) -> Self {
+    // Staying at the node keeps the node it was reached from
+    Msg { w : a.w + b.w, p : if b.p == Msg::STAY { a.p } else { b.p } }
+  }
+  fn mul_add(acc : &mut Self, a : &Self, b : &Self) {
+    let w = a.w + b.w;
+    if w < acc.w {
+      *acc = Msg { w, p : if b.p == Msg::STAY { a.p } else { b.p } };
     }
-    temp
   }
 
-  fn neutral_matrix (rows : usize, cols : usize) -> Matrix<Self> {
-    (0..rows).map(|j| (0..cols).map(|i| Msg{ 
-      w : if i == j {0.0} else {-1.0}, 
-      p : i 
-    }).collect()).collect()
+  // Nodes without a path are reached from themselves
+  fn zero_matrix(rows : usize, cols : usize) -> Matrix<Self> {
+    (0..rows).map(|_| (0..cols).map(|j| Msg { w : f64::INFINITY, p : j }).collect()).collect()
+  }
+
+  fn identity_matrix(size : usize) -> Matrix<Self> {
+    (0..size).map(|i| (0..size).map(|j|
+      if i == j { Msg::one() } else { Msg { w : f64::INFINITY, p : j } }
+    ).collect()).collect()
   }
 }
 
 impl Sendable for () {}
 
-impl Sendable for isize {}
-impl Multiplicable for isize {
-  fn initial_c (a : &Matrix<Self>, b : &Matrix<Self>) -> Matrix<Self> {
-    a.iter().map(|_| b[0].iter().map(|_| 0).collect()).collect()
+impl Sendable for isize {
+  fn is_blank(&self) -> bool {
+    self.is_zero()
+  }
+}
+impl Semiring for isize {
+  fn zero() -> Self {
+    0
   }
-  fn singleton_matrix(a : Self, b : Self, c : Self) -> Self {
-    c + a * b
+  fn is_zero(&self) -> bool {
+    *self == 0
   }
-  fn neutral_matrix (rows : usize, cols : usize) -> Matrix<Self> {
-    (0..rows).map(|_| (0..cols).map(|_| 0).collect()).collect()
+  fn one() -> Self {
+    1
+  }
+  fn add(a : Self, b : Self) -> Self {
+    a + b
+  }
+  fn mul(a : Self, b : Self) -> Self {
+    a * b
   }
 }
 
-impl Sendable for usize {}
-impl Multiplicable for usize {
-  fn initial_c (a : &Matrix<Self>, b : &Matrix<Self>) -> Matrix<Self> {
-    a.iter().map(|_| b[0].iter().map(|_| 0).collect()).collect()
+impl Ring for isize {
+  fn subtract(a : Self, b : Self) -> Self {
+    a - b
   }
-  fn singleton_matrix(a : Self, b : Self, c : Self) -> Self {
-    c + a * b
+}
+
+impl Sendable for usize {
+  fn is_blank(&self) -> bool {
+    self.is_zero()
   }
-  fn neutral_matrix (rows : usize, cols : usize) -> Matrix<Self> {
-    (0..rows).map(|_| (0..cols).map(|_| 0).collect()).collect()
+}
+impl Semiring for usize {
+  fn zero() -> Self {
+    0
+  }
+  fn is_zero(&self) -> bool {
+    *self == 0
+  }
+  fn one() -> Self {
+    1
+  }
+  fn add(a : Self, b : Self) -> Self {
+    a + b
+  }
+  fn mul(a : Self, b : Self) -> Self {
+    a * b
   }
 }
 
-impl Sendable for f64 {}
-impl Multiplicable for f64 {
-  fn initial_c (a : &Matrix<Self>, b : &Matrix<Self>) -> Matrix<Self> {
-    a.iter().map(|_| b[0].iter().map(|_| 0.0).collect()).collect()
+impl Sendable for f64 {
+  fn is_blank(&self) -> bool {
+    self.is_zero()
+  }
+}
+impl Semiring for f64 {
+  fn zero() -> Self {
+    0.0
+  }
+  fn is_zero(&self) -> bool {
+    *self == 0.0
+  }
+  fn one() -> Self {
+    1.0
   }
-  fn singleton_matrix(a : Self, b : Self, c : Self) -> Self {
-    c + a * b
+  fn add(a : Self, b : Self) -> Self {
+    a + b
   }
-  fn neutral_matrix (rows : usize, cols : usize) -> Matrix<Self> {
-    (0..rows).map(|_| (0..cols).map(|_| 0.0).collect()).collect()
+  fn mul(a : Self, b : Self) -> Self {
+    a * b
+  }
+}
+
+impl Ring for f64 {
+  fn subtract(a : Self, b : Self) -> Self {
+    a - b
   }
 }
 
-impl<T:Sendable> Sendable for Vec<Vec<T>> {}
+impl<T:Sendable> Sendable for Vec<Vec<T>> {
+  fn payload_size(&self) -> usize {
+    self.iter().flat_map(|row| row.iter()).map(Sendable::payload_size).sum()
+  }
+  /// Blocks are sent in whichever of the dense and CSR forms is smaller
+  fn message_size(&self) -> usize {
+    let (dense, csr) = block_sizes(self);
+    dense.min(csr)
+  }
+}
 pub type Matrix<T> = Vec<Vec<T>>;
 
 impl Sendable for Duration {}
 
-impl<X:Sendable> Sendable for Option<X> {}
+impl<X:Sendable> Sendable for Option<X> {
+  fn payload_size(&self) -> usize {
+    self.as_ref().map_or(0, Sendable::payload_size)
+  }
+}
 
-impl<X:Sendable, Y:Sendable> Sendable for (X,Y) {}
-impl<X:Sendable, Y:Sendable, Z:Sendable> Sendable for (X,Y,Z) {}
+impl<X:Sendable, Y:Sendable> Sendable for (X,Y) {
+  fn payload_size(&self) -> usize {
+    self.0.payload_size() + self.1.payload_size()
+  }
+}
+impl<X:Sendable, Y:Sendable, Z:Sendable> Sendable for (X,Y,Z) {
+  fn payload_size(&self) -> usize {
+    self.0.payload_size() + self.1.payload_size() + self.2.payload_size()
+  }
+}
@@ -0,0 +1,163 @@
+use serde::{Serialize,Deserialize};
+use crate::broadcast::Sendable;
+use crate::matmul::Semiring;
+
+/// Tropical min-plus semiring, where products of adjacency matrices give the
+/// lengths of shortest paths. Missing edges are infinitely long.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct MinPlus(pub f64);
+
+impl Sendable for MinPlus {
+  fn is_blank(&self) -> bool {
+    self.is_zero()
+  }
+}
+impl Semiring for MinPlus {
+  const IDEMPOTENT : bool = true;
+
+  fn zero() -> Self {
+    MinPlus(f64::INFINITY)
+  }
+  fn is_zero(&self) -> bool {
+    self.0 == f64::INFINITY
+  }
+  fn one() -> Self {
+    MinPlus(0.0)
+  }
+  fn add(a : Self, b : Self) -> Self {
+    MinPlus(a.0.min(b.0))
+  }
+  fn mul(a : Self, b : Self) -> Self {
+    MinPlus(a.0 + b.0)
+  }
+}
+
+/// Tropical max-plus semiring, where products of adjacency matrices give the
+/// lengths of longest paths, as in critical path scheduling
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct MaxPlus(pub f64);
+
+impl Sendable for MaxPlus {
+  fn is_blank(&self) -> bool {
+    self.is_zero()
+  }
+}
+impl Semiring for MaxPlus {
+  const IDEMPOTENT : bool = true;
+
+  fn zero() -> Self {
+    MaxPlus(f64::NEG_INFINITY)
+  }
+  fn is_zero(&self) -> bool {
+    self.0 == f64::NEG_INFINITY
+  }
+  fn one() -> Self {
+    MaxPlus(0.0)
+  }
+  fn add(a : Self, b : Self) -> Self {
+    MaxPlus(a.0.max(b.0))
+  }
+  fn mul(a : Self, b : Self) -> Self {
+    MaxPlus(a.0 + b.0)
+  }
+}
+
+impl Sendable for bool {
+  fn is_blank(&self) -> bool {
+    self.is_zero()
+  }
+}
+/// Boolean semiring, where products of adjacency matrices give reachability
+impl Semiring for bool {
+  const IDEMPOTENT : bool = true;
+
+  fn zero() -> Self {
+    false
+  }
+  fn is_zero(&self) -> bool {
+    !*self
+  }
+  fn one() -> Self {
+    true
+  }
+  fn add(a : Self, b : Self) -> Self {
+    a || b
+  }
+  fn mul(a : Self, b : Self) -> Self {
+    a && b
+  }
+}
+
+/// Bottleneck (max-min) semiring, where products of capacity matrices give
+/// the widest path, the one whose narrowest edge is widest
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct MaxMin(pub f64);
+
+impl Sendable for MaxMin {
+  fn is_blank(&self) -> bool {
+    self.is_zero()
+  }
+}
+impl Semiring for MaxMin {
+  const IDEMPOTENT : bool = true;
+
+  fn zero() -> Self {
+    MaxMin(f64::NEG_INFINITY)
+  }
+  fn is_zero(&self) -> bool {
+    self.0 == f64::NEG_INFINITY
+  }
+  fn one() -> Self {
+    MaxMin(f64::INFINITY)
+  }
+  fn add(a : Self, b : Self) -> Self {
+    MaxMin(a.0.max(b.0))
+  }
+  fn mul(a : Self, b : Self) -> Self {
+    MaxMin(a.0.min(b.0))
+  }
+}
+
+/// Min-plus semiring which also counts the shortest paths. Summing two
+/// terms of the same length adds their counts, so it is not idempotent.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MinPlusCount {
+  pub length : f64,
+  pub count : usize,
+}
+
+impl MinPlusCount {
+  /// A single path of `length`
+  pub fn new(length : f64) -> Self {
+    MinPlusCount { length, count : 1 }
+  }
+}
+
+impl Sendable for MinPlusCount {
+  fn is_blank(&self) -> bool {
+    self.is_zero()
+  }
+}
+impl Semiring for MinPlusCount {
+  fn zero() -> Self {
+    MinPlusCount { length : f64::INFINITY, count : 0 }
+  }
+  fn is_zero(&self) -> bool {
+    self.length == f64::INFINITY && self.count == 0
+  }
+  fn one() -> Self {
+    MinPlusCount { length : 0.0, count : 1 }
+  }
+  fn add(a : Self, b : Self) -> Self {
+    if a.length < b.length {
+      a
+    } else if b.length < a.length {
+      b
+    } else {
+      MinPlusCount { length : a.length, count : a.count + b.count }
+    }
+  }
+  fn mul(a : Self, b : Self) -> Self {
+    MinPlusCount { length : a.length + b.length, count : a.count * b.count }
+  }
+}